@@ -0,0 +1,141 @@
+mod keyword_list;
+mod options;
+
+use std::ptr::null_mut;
+
+/* Generates, on top of the case-insensitive lookup function, a function
+   returning the index of the matched keyword in input order, and one that
+   rewrites its argument to the canonical spelling.  The canonical spelling
+   of a keyword is the one of its first occurrence in the input; later
+   occurrences differing only by case have been dropped by Input.  */
+struct Canonical_Index {
+
+    /* The canonical spellings, in input order.  */
+    _keywords: Vec<Vec<u8>>,
+
+    /* The return type of the lookup function.  */
+    _return_type: String
+}
+
+impl Canonical_Index {
+
+    pub fn new(head: *mut Keyword_List, return_type: &str) -> Canonical_Index {
+        let mut keywords: Vec<Vec<u8>> = Vec::new();
+        let mut temp: *mut Keyword_List = head;
+        while (temp != null_mut()) {
+            keywords.push((*(*temp).first())._allchars.clone());
+            temp = (*temp).rest();
+        }
+
+        Canonical_Index {
+            _keywords: keywords,
+            _return_type: if option[TYPE] { String::from(return_type) } else { String::from("const char *") }
+        }
+    }
+
+    /* Writes a C string literal.  */
+    fn output_string(s: &[u8]) {
+        print!("\"");
+        for &c in s {
+            if (c == b'"' || c == b'\\') {
+                print!("\\{}", c as char);
+            } else if (c >= 0x20 && c < 0x7f && c != b'?') {
+                print!("{}", c as char);
+            } else {
+                print!("\\{:03o}", c);
+            }
+        }
+        print!("\"");
+    }
+
+    /* Writes the two functions, named after the lookup function with the
+       suffixes "_canonical_index" and "_canonicalize".  The index of the
+       matched entry is found by a binary search over the spellings, sorted
+       the way strcmp orders them; the lookup function has already rejected
+       anything that is not a keyword.  The generated code uses memcpy and
+       strcmp, so it needs <string.h> (see --includes).  */
+    pub fn output(&self) {
+        let lookup_name: String = if option[CPLUSPLUS] {
+            format!("{}::{}", option_text(option.get_class_name()), option_text(option.get_function_name()))
+        } else {
+            option_text(option.get_function_name())
+        };
+        let function_name: String = option_text(option.get_function_name());
+        let array_name: String = format!("{}_canonical", option_text(option.get_wordlist_name()));
+        let const_string: &str = if option[KRC] { "" } else { "const " };
+        let const_readonly_array: &str = if option[CONST] { "const " } else { "" };
+
+        let mut sorted: Vec<usize> = (0..self._keywords.len()).collect();
+        sorted.sort_by(|&a, &b| self._keywords[a].cmp(&self._keywords[b]));
+
+        print!("\n/* Canonical keyword spellings, in input order.  */\n");
+        print!("static {}char *{}{}[] =\n  {{\n", const_string, const_readonly_array, array_name);
+        for (i, keyword) in self._keywords.iter().enumerate() {
+            print!("    ");
+            Canonical_Index::output_string(keyword);
+            print!("{} /* {} */\n", if i + 1 < self._keywords.len() { "," } else { "" }, i);
+        }
+        print!("  }};\n\n");
+
+        print!("/* Indices into {}, sorted by spelling.  */\n", array_name);
+        print!("static {}unsigned int {}_sorted[] =\n  {{\n    ", const_readonly_array, array_name);
+        for (i, index) in sorted.iter().enumerate() {
+            if (i > 0) {
+                print!(",{}", if i % 16 == 0 { "\n    " } else { " " });
+            }
+            print!("{}", index);
+        }
+        print!("\n  }};\n\n");
+
+        /* The index lookup.  */
+        print!("int\n");
+        if (option[KRC] || option[C]) {
+            print!("{}_canonical_index (str, len)\n     register const char *str;\n     register unsigned int len;\n",
+                   function_name);
+        } else {
+            print!("{}_canonical_index (register const char *str, register unsigned int len)\n",
+                   function_name);
+        }
+        print!("{{\n");
+        print!("  {}result = {} (str, len);\n", self._return_type, lookup_name);
+        print!("  {}char *key;\n", const_string);
+        print!("  unsigned int lo = 0;\n");
+        print!("  unsigned int hi = {};\n\n", self._keywords.len());
+        print!("  if (!result)\n    return -1;\n");
+        if (option[TYPE] && option[SHAREDLIB]) {
+            /* The key field is an offset into the string pool.  */
+            print!("  key = {} + result->{};\n", option_text(option.get_stringpool_name()),
+                   option_text(option.get_slot_name()));
+        } else if (option[TYPE]) {
+            print!("  key = result->{};\n", option_text(option.get_slot_name()));
+        } else {
+            print!("  key = result;\n");
+        }
+        print!("  while (lo < hi)\n");
+        print!("    {{\n");
+        print!("      unsigned int mid = (lo + hi) / 2;\n");
+        print!("      int cmp = strcmp (key, {}[{}_sorted[mid]]);\n\n", array_name, array_name);
+        print!("      if (cmp == 0)\n        return {}_sorted[mid];\n", array_name);
+        print!("      if (cmp < 0)\n        hi = mid;\n      else\n        lo = mid + 1;\n");
+        print!("    }}\n");
+        print!("  return -1;\n");
+        print!("}}\n\n");
+
+        /* The rewrite helper.  Case-only variants have the same length as
+           the keyword, so the spelling is copied in place.  */
+        print!("int\n");
+        if (option[KRC] || option[C]) {
+            print!("{}_canonicalize (str, len)\n     register char *str;\n     register unsigned int len;\n",
+                   function_name);
+        } else {
+            print!("{}_canonicalize (register char *str, register unsigned int len)\n",
+                   function_name);
+        }
+        print!("{{\n");
+        print!("  int index = {}_canonical_index (str, len);\n\n", function_name);
+        print!("  if (index >= 0)\n    memcpy (str, {}[index], len);\n", array_name);
+        print!("  return index;\n");
+        print!("}}\n");
+    }
+
+}
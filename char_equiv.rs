@@ -0,0 +1,79 @@
+mod options;
+
+/* Character equivalence classes, given with %char-equiv "CHARS": the
+   characters of a class are one character for the hash function and for
+   the comparison in the lookup.  They combine with --ignore-case, so that
+   with %char-equiv "-_" the keywords foo-bar, foo_bar and FOO_BAR are one
+   keyword.  */
+
+/* Number of byte values.  */
+const FOLD_SIZE: usize = 256;
+
+/* Returns whether any %char-equiv was given.  */
+pub fn has_char_equivs() -> bool {
+    return !option.get_char_equivs().is_empty();
+}
+
+/* Returns the map of each byte to the representative of its class: the
+   smallest byte of the class, after lower-casing with --ignore-case.
+   Classes sharing a character are merged.  compute_alpha_unify unifies
+   the selected characters of the search with it, through
+   target_fold_map.  */
+pub fn char_fold_map() -> Vec<u8> {
+    let mut map: Vec<u8> = (0..FOLD_SIZE).map(|c| c as u8).collect();
+    if (option[UPPERLOWER]) {
+        for c in b'A'..=b'Z' {
+            map[c as usize] = c.to_ascii_lowercase();
+        }
+    }
+
+    for class in option.get_char_equivs().iter() {
+        let members: Vec<u8> = class.iter().map(|&c| map[c as usize]).collect();
+        let representative: u8 = *members.iter().min().unwrap();
+        for c in 0..FOLD_SIZE {
+            if (members.contains(&map[c])) {
+                map[c] = representative;
+            }
+        }
+    }
+    return map;
+}
+
+/* Returns BYTES with each character replaced by its representative.  */
+pub fn fold(bytes: &[u8], map: &Vec<u8>) -> Vec<u8> {
+    return bytes.iter().map(|&c| map[c as usize]).collect();
+}
+
+/* Emits the fold table and the comparison functions that the C lookup
+   uses instead of strcmp/strncmp, or gperf_case_strcmp/strncmp with
+   --ignore-case, when %char-equiv was given.  */
+pub fn output_fold_compare() {
+    let map: Vec<u8> = char_fold_map();
+    let const_readonly: &str = if option[KRC] { "" } else { "const " };
+
+    print!("static {}unsigned char gperf_fold[] =\n  {{", const_readonly);
+    for (c, &value) in map.iter().enumerate() {
+        if (c > 0) {
+            print!(",");
+        }
+        print!("{}", if c % 16 == 0 { "\n    " } else { " " });
+        print!("{:3}", value);
+    }
+    print!("\n  }};\n\n");
+
+    if (option[KRC]) {
+        print!("static int\ngperf_fold_strcmp (s1, s2)\n     register char *s1;\n     register char *s2;\n");
+    } else {
+        print!("static int\ngperf_fold_strcmp (register const char *s1, register const char *s2)\n");
+    }
+    print!("{{\n  for (;;)\n    {{\n      unsigned char c1 = gperf_fold[(unsigned char)*s1++];\n      unsigned char c2 = gperf_fold[(unsigned char)*s2++];\n      if (c1 != 0 && c1 == c2)\n        continue;\n      return (int)c1 - (int)c2;\n    }}\n}}\n\n");
+
+    if (option[COMP] || option[LENTABLE]) {
+        if (option[KRC]) {
+            print!("static int\ngperf_fold_strncmp (s1, s2, n)\n     register char *s1;\n     register char *s2;\n     register unsigned int n;\n");
+        } else {
+            print!("static int\ngperf_fold_strncmp (register const char *s1, register const char *s2, register unsigned int n)\n");
+        }
+        print!("{{\n  for (; n > 0;)\n    {{\n      unsigned char c1 = gperf_fold[(unsigned char)*s1++];\n      unsigned char c2 = gperf_fold[(unsigned char)*s2++];\n      if (c1 != 0 && c1 == c2)\n        {{\n          n--;\n          continue;\n        }}\n      return (int)c1 - (int)c2;\n    }}\n  return 0;\n}}\n\n");
+    }
+}
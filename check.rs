@@ -0,0 +1,202 @@
+mod keyword;
+mod keyword_list;
+mod options;
+
+use std::process;
+use std::ptr::null_mut;
+use std::fs::File;
+use std::io::Read;
+
+/* Marks the comment of the generated file holding the digest.  */
+const DIGEST_MARKER: &str = "gperf digest: ";
+
+/* Lines of context around each change in the diff.  */
+const DIFF_CONTEXT: usize = 3;
+
+/* Largest table of the longest common subsequence, in entries, that the
+   diff builds.  Beyond it, the changed part is shown as one hunk.  */
+const DIFF_MAX_TABLE: usize = 1 << 22;
+
+/* 64-bit FNV-1a.  */
+struct Digest {
+    _value: u64
+}
+
+impl Digest {
+
+    fn new() -> Digest {
+        Digest { _value: 0xcbf29ce484222325 }
+    }
+
+    /* Adds BYTES, followed by a separator, so that consecutive strings
+       cannot run into each other.  */
+    fn add(&mut self, bytes: &[u8]) {
+        for &b in bytes.iter().chain(std::iter::once(&0u8)) {
+            self._value ^= b as u64;
+            self._value = self._value.wrapping_mul(0x100000001b3);
+        }
+    }
+
+}
+
+/* Returns the digest of everything the output depends on, besides the
+   tool itself: the keywords of LISTS, the lists of all keyword sets, with
+   their initializers, and the effective options, the same list the
+   banner shows.  */
+pub fn input_digest(lists: &Vec<*mut Keyword_List>) -> u64 {
+    let mut digest: Digest = Digest::new();
+
+    for &head in lists.iter() {
+        let mut temp: *mut Keyword_List = head;
+        while (temp != null_mut()) {
+            let keyword: *mut Keyword = (*temp).first();
+            digest.add(&(*keyword)._allchars);
+            digest.add(&(*keyword)._rest);
+            temp = (*temp).rest();
+        }
+        /* Separates the sets.  */
+        digest.add(&[]);
+    }
+
+    for argument in option.effective_arguments().iter() {
+        digest.add(argument.as_bytes());
+    }
+    return digest._value;
+}
+
+/* Emits the digest line, near the top of the output, so that --check
+   can tell why a file is stale.  LISTS are the keyword lists of all
+   sets, from Input::all_keyword_lists.  */
+pub fn output_digest(lists: &Vec<*mut Keyword_List>) {
+    print!("{}", option.comment_line(&format!("{}input {:016x}, version {}", DIGEST_MARKER, input_digest(lists), version_string)));
+}
+
+/* Returns the input digest and the version recorded in TEXT, if any.  */
+fn parse_digest(text: &str) -> Option<(String, String)> {
+    let fields: &str = text.lines()
+        .filter_map(|line| line.strip_prefix("/* ").or_else(|| line.strip_prefix("# ")))
+        .find_map(|line| line.strip_prefix(DIGEST_MARKER))?
+        .trim_end_matches(" */");
+    let (input, version) = fields.split_once(", version ")?;
+    return Some((String::from(input.trim_start_matches("input ")), String::from(version)));
+}
+
+/* Returns the edit script turning OLD into NEW: for each line, ' ' if it
+   is kept, '-' if removed, '+' if added.  */
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    let mut prefix: usize = 0;
+    while (prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix]) {
+        prefix += 1;
+    }
+    let mut suffix: usize = 0;
+    while (suffix < old.len() - prefix && suffix < new.len() - prefix
+           && old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix]) {
+        suffix += 1;
+    }
+    let a: &[&str] = &old[prefix..old.len() - suffix];
+    let b: &[&str] = &new[prefix..new.len() - suffix];
+
+    let mut result: Vec<(char, &str)> = old[..prefix].iter().map(|&line| (' ', line)).collect();
+    if ((a.len() + 1).saturating_mul(b.len() + 1) > DIFF_MAX_TABLE) {
+        result.extend(a.iter().map(|&line| ('-', line)));
+        result.extend(b.iter().map(|&line| ('+', line)));
+        result.extend(old[old.len() - suffix..].iter().map(|&line| (' ', line)));
+        return result;
+    }
+
+    /* Longest common subsequence of the changed middle part.  */
+    let mut lcs: Vec<Vec<u32>> = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { std::cmp::max(lcs[i + 1][j], lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while (i < a.len() || j < b.len()) {
+        if (i < a.len() && j < b.len() && a[i] == b[j]) {
+            result.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if (j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j])) {
+            result.push(('+', b[j]));
+            j += 1;
+        } else {
+            result.push(('-', a[i]));
+            i += 1;
+        }
+    }
+    result.extend(old[old.len() - suffix..].iter().map(|&line| (' ', line)));
+    return result;
+}
+
+/* Prints the unified diff of OLD and NEW on the standard error.  */
+fn print_diff(old: &str, new: &str, file_name: &str) {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let script: Vec<(char, &str)> = diff_lines(&old_lines, &new_lines);
+
+    eprintln!("--- {}\n+++ {} (regenerated)", file_name, file_name);
+
+    let mut k: usize = 0;
+    while (k < script.len()) {
+        if (script[k].0 == ' ') {
+            k += 1;
+            continue;
+        }
+
+        /* A hunk: the changes from K on, until DIFF_CONTEXT * 2 unchanged
+           lines separate them from the next one.  */
+        let start: usize = k.saturating_sub(DIFF_CONTEXT);
+        let mut end: usize = k;
+        let mut unchanged: usize = 0;
+        while (end < script.len() && unchanged <= 2 * DIFF_CONTEXT) {
+            unchanged = if script[end].0 == ' ' { unchanged + 1 } else { 0 };
+            end += 1;
+        }
+        end -= unchanged.saturating_sub(DIFF_CONTEXT);
+
+        let old_start: usize = script[..start].iter().filter(|e| e.0 != '+').count();
+        let new_start: usize = script[..start].iter().filter(|e| e.0 != '-').count();
+        let old_count: usize = script[start..end].iter().filter(|e| e.0 != '+').count();
+        let new_count: usize = script[start..end].iter().filter(|e| e.0 != '-').count();
+        eprintln!("@@ -{},{} +{},{} @@", old_start + 1, old_count, new_start + 1, new_count);
+        for &(op, line) in script[start..end].iter() {
+            eprintln!("{}{}", op, line);
+        }
+        k = end;
+    }
+}
+
+/* Implements --check: compares GENERATED, the output of this run, with
+   the contents of FILE_NAME.  Returns if they are the same; otherwise
+   reports why the file is stale, prints the differences and exits with
+   status 1.  Nothing is written.  */
+pub fn check_output(generated: &[u8], file_name: *const char) {
+    let name: String = option_text(file_name);
+    let mut existing: Vec<u8> = Vec::new();
+    let read: bool = match File::open(&name) {
+        Ok(mut stream) => stream.read_to_end(&mut existing).is_ok(),
+        Err(_) => false
+    };
+    if (!read) {
+        eprintln!("{}: stale: cannot read {}\n", program_name, name);
+        process::exit(1);
+    }
+
+    if (existing == generated) {
+        return;
+    }
+
+    let old: String = String::from_utf8_lossy(&existing).into_owned();
+    let new: String = String::from_utf8_lossy(generated).into_owned();
+    let reason: &str = match (parse_digest(&old), parse_digest(&new)) {
+        (Some((old_input, _)), Some((new_input, _))) if old_input != new_input => "input changed",
+        (Some((_, old_version)), Some((_, new_version))) if old_version != new_version => "tool version changed",
+        (None, _) => "no digest in the output file",
+        _ => "output differs"
+    };
+    eprintln!("{}: {}: stale: {}", program_name, name, reason);
+    print_diff(&old, &new, &name);
+    process::exit(1);
+}
@@ -0,0 +1,53 @@
+mod options;
+
+use std::ptr::null_mut;
+
+/* Support for %compare-function=NAME and --compare-function=NAME: the
+   lookup function calls the user's NAME instead of the usual comparison to
+   decide whether the candidate keyword matches.  The hash is still
+   computed on the raw bytes, so NAME may only treat strings as equal that
+   hash alike, e.g. by comparing normalized forms of keywords that are
+   already normalized.
+
+   In every output language NAME returns 0 if the keyword matches the
+   input, like strncmp, and nonzero otherwise.  In C and C++ it is called
+   as NAME (keyword, str, len), where str and len are the arguments of
+   the lookup function, and the user defines it.  In Go it is called as
+   NAME(keyword, s) and the user defines it in the same package.  In Java
+   and Python, where the generated file cannot be added to, NAME is a
+   variable of the generated class or module that the user assigns
+   before the first lookup: a ToIntBiFunction<String, String> in Java, a
+   function of two str in Python.  */
+
+/* Returns the user's comparison function, or None for strcmp.  */
+pub fn compare_function() -> Option<String> {
+    let name: *const char = option.get_compare_function();
+    if (name == null_mut()) {
+        return None;
+    }
+    return Some(option_text(name));
+}
+
+/* Emits the prototype of the user's comparison function, before the
+   lookup function.  */
+pub fn output_compare_prototype() {
+    let name: String = match compare_function() {
+        Some(name) => name,
+        None => return
+    };
+
+    if (option[KRC] || option[C]) {
+        print!("extern int {} ();\n", name);
+    } else {
+        print!("extern int {} (const char *keyword, const char *str, unsigned int len);\n", name);
+    }
+}
+
+/* Returns the condition under which KEYWORD, an expression for the
+   candidate keyword, matches the input STR of length LEN, or None if the
+   usual strcmp/strncmp comparison applies.  The first-character shortcut
+   of the usual comparison must not be put in front of it, since NAME may
+   consider different bytes equal.  */
+pub fn compare_condition(keyword: &str, str: &str, len: &str) -> Option<String> {
+    return compare_function().map(|name| format!("!{} ({}, {}, {})", name, keyword, str, len));
+}
@@ -0,0 +1,328 @@
+mod header_output;
+mod keyword_list;
+mod language_output;
+mod options;
+mod self_test;
+mod struct_fields;
+
+use std::process;
+use std::ptr::null_mut;
+use std::fs::File;
+use std::io::Write;
+
+/* Writes the test file requested with --emit-tests: a program in the
+   output language that checks that every keyword is found, with the
+   values of its struct fields, and that the near misses of the self-test
+   are rejected.  Unlike --self-test, nothing is compiled here; the tests
+   are meant to be built and run with the rest of the user's project, on
+   the machine the generated code is for.  The C and C++ test is a
+   translation unit of its own, linked with the object of the generated
+   code: it #includes the header of --output-header, or else declares the
+   struct and the lookup function itself.  */
+struct Test_Output<'a> {
+
+    /* The keywords with the initializers of their other fields, in input
+       order.  */
+    _keywords: Vec<(Vec<u8>, Vec<u8>)>,
+
+    /* For each keyword, whether other keywords are equal to it after case
+       and %char-equiv folding.  The lookup may then return any of them,
+       so only the key is checked.  */
+    _duplicate: Vec<bool>,
+
+    /* Strings that are not keywords.  */
+    _near_misses: Vec<Vec<u8>>,
+
+    /* The fields of the struct declaration; the first one holds the
+       keyword.  Empty without --struct-type.  */
+    _fields: &'a Vec<Struct_Field>,
+
+    /* The struct declaration, for the C test without a header, or
+       empty.  */
+    _struct_decl: String,
+
+    /* The return type of the C lookup function.  */
+    _return_type: String,
+
+    /* For Go, Java and Python, the generator of the lookup code, which
+       writes the literals the same way in the tests.  */
+    _language_output: Option<&'a Language_Output<'a>>
+}
+
+impl<'a> Test_Output<'a> {
+
+    pub fn new(head: *mut Keyword_List, fields: &'a Vec<Struct_Field>, struct_decl: &str, return_type: &str,
+               language_output: Option<&'a Language_Output<'a>>) -> Test_Output<'a> {
+        let mut keywords: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut temp: *mut Keyword_List = head;
+        while (temp != null_mut()) {
+            let keyword: *mut Keyword = (*temp).first();
+            keywords.push(((*keyword)._allchars.clone(), (*keyword)._rest.clone()));
+            temp = (*temp).rest();
+        }
+
+        let folded: Vec<Vec<u8>> = keywords.iter().map(|(k, _)| Self_Test::fold(k)).collect();
+        let duplicate: Vec<bool> = folded.iter().map(|f| folded.iter().filter(|g| *g == f).count() > 1).collect();
+
+        let allchars: Vec<Vec<u8>> = keywords.iter().map(|(k, _)| k.clone()).collect();
+        let mut near_misses: Vec<Vec<u8>> = Self_Test::near_misses(&allchars);
+        if (option[JAVA] || option[PYTHON]) {
+            /* Their strings hold characters, so a near miss that is not
+               UTF-8 cannot be passed to the lookup.  */
+            near_misses.retain(|s| std::str::from_utf8(s).is_ok());
+        }
+
+        Test_Output {
+            _keywords: keywords,
+            _duplicate: duplicate,
+            _near_misses: near_misses,
+            _fields: fields,
+            _struct_decl: if option[TYPE] && !option[NOTYPE] { String::from(struct_decl) } else { String::new() },
+            _return_type: if option[TYPE] {
+                String::from(return_type)
+            } else if option[KRC] {
+                String::from("char *")
+            } else {
+                String::from("const char *")
+            },
+            _language_output: language_output
+        }
+    }
+
+    fn language_output(&self) -> &Language_Output<'a> {
+        return self._language_output.expect("--emit-tests needs the lookup code generator");
+    }
+
+    fn write_c(&self, stream: &mut File, file_name: &str) -> std::io::Result<()> {
+        let function_name: String = if option[CPLUSPLUS] {
+            format!("{}::{}", option_text(option.get_class_name()), option_text(option.get_function_name()))
+        } else {
+            option_text(option.get_function_name())
+        };
+        /* With --pic the key field is an offset into the string pool, which
+           is static to the generated code, so the key is not checked.  */
+        let key: Option<String> = if !option[TYPE] {
+            Some(String::from("r"))
+        } else if option[SHAREDLIB] {
+            None
+        } else {
+            Some(format!("r->{}", option_text(option.get_slot_name())))
+        };
+
+        writeln!(stream, "/* Tests of {}, generated by {}.  */\n", option_text(option.get_output_file_name()), program_name)?;
+        writeln!(stream, "#include <stdio.h>\n#include <string.h>")?;
+        if (option[UPPERLOWER]) {
+            writeln!(stream, "#include <strings.h>")?;
+        }
+        if (option.get_output_header_file_name() != null_mut()) {
+            writeln!(stream, "#include \"{}\"",
+                     Header_Output::include_path(&option_text(option.get_output_header_file_name()), file_name))?;
+        } else {
+            Header_Output::write_struct_decl(stream, &self._struct_decl)?;
+            Header_Output::write_prototypes(stream, &self._return_type)?;
+        }
+        writeln!(stream, "\nstatic int failures;\n")?;
+        if (option[KRC]) {
+            writeln!(stream, "static void\ncheck (ok, what, str)\n     int ok;\n     char *what;\n     char *str;")?;
+        } else {
+            writeln!(stream, "static void\ncheck (int ok, const char *what, const char *str)")?;
+        }
+        writeln!(stream, "{{\n  if (!ok)\n    {{\n      fprintf (stderr, \"%s: %s\\n\", what, str);\n      failures++;\n    }}\n}}\n")?;
+        writeln!(stream, "int\nmain ()\n{{\n  {}r;\n", self._return_type)?;
+
+        for (i, (keyword, rest)) in self._keywords.iter().enumerate() {
+            let literal: String = Self_Test::c_string(keyword);
+            writeln!(stream, "  r = {} ({}, {});", function_name, literal, keyword.len())?;
+            writeln!(stream, "  check (r != 0, \"keyword not found\", {});", literal)?;
+
+            let mut conditions: Vec<(String, String)> = Vec::new();
+            if (!self._duplicate[i]) {
+                if let Some(key) = &key {
+                    conditions.push((format!("memcmp ({}, {}, {}) == 0", key, literal, keyword.len()),
+                                     String::from("keyword mapped to wrong entry")));
+                }
+                if (option[TYPE] && self._fields.len() > 1) {
+                    /* The fields without an initializer are zero.  */
+                    let initializers: Vec<String> = split_initializers(&String::from_utf8_lossy(rest));
+                    for (j, field) in self._fields[1..].iter().enumerate() {
+                        let initializer: &str = initializers.get(j).map_or("0", |s| s.as_str());
+                        if let Some(condition) = Self_Test::c_field_condition("r", field, initializer) {
+                            conditions.push((condition, format!("wrong value of field {}", field._name)));
+                        }
+                    }
+                }
+            } else if (option[UPPERLOWER] && !has_char_equivs()) {
+                if let Some(key) = &key {
+                    conditions.push((format!("strncasecmp ({}, {}, {}) == 0", key, literal, keyword.len()),
+                                     String::from("keyword mapped to wrong entry")));
+                }
+            }
+            if (!conditions.is_empty()) {
+                writeln!(stream, "  if (r != 0)\n    {{")?;
+                for (condition, what) in conditions.iter() {
+                    writeln!(stream, "      check ({}, \"{}\", {});", condition, what, literal)?;
+                }
+                writeln!(stream, "    }}")?;
+            }
+        }
+        writeln!(stream, "")?;
+
+        for near_miss in self._near_misses.iter() {
+            let literal: String = Self_Test::c_string(near_miss);
+            writeln!(stream, "  check ({} ({}, {}) == 0, \"non-keyword accepted\", {});",
+                     function_name, literal, near_miss.len(), literal)?;
+        }
+        writeln!(stream, "\n  return failures != 0;\n}}")?;
+        return Ok(());
+    }
+
+    fn write_go(&self, stream: &mut File) -> std::io::Result<()> {
+        let language: &Language_Output = self.language_output();
+        let package: String = identifier_words(&option_text(option.get_class_name())).concat();
+        let lookup: String = pascal_case(&option_text(option.get_function_name()));
+
+        writeln!(stream, "// Code generated by gperf; DO NOT EDIT.\n\npackage {}\n\nimport \"testing\"\n", package)?;
+
+        writeln!(stream, "func TestKeywords(t *testing.T) {{")?;
+        for (i, (keyword, rest)) in self._keywords.iter().enumerate() {
+            let literal: String = language.string_literal(keyword);
+            if (!option[TYPE]) {
+                writeln!(stream, "\tif !{}({}) {{", lookup, literal)?;
+            } else if (self._duplicate[i]) {
+                writeln!(stream, "\tif _, ok := {}({}); !ok {{", lookup, literal)?;
+            } else {
+                let mut values: Vec<String> = vec![literal.clone()];
+                values.extend(language.payload(rest));
+                writeln!(stream, "\tif e, ok := {}({}); !ok || *e != (Entry{{{}}}) {{", lookup, literal, values.join(", "))?;
+            }
+            writeln!(stream, "\t\tt.Errorf(\"keyword %q not found or mapped to the wrong entry\", {})\n\t}}", literal)?;
+        }
+        writeln!(stream, "}}\n")?;
+
+        writeln!(stream, "func TestNonKeywords(t *testing.T) {{")?;
+        for near_miss in self._near_misses.iter() {
+            let literal: String = language.string_literal(near_miss);
+            if (option[TYPE]) {
+                writeln!(stream, "\tif _, ok := {}({}); ok {{", lookup, literal)?;
+            } else {
+                writeln!(stream, "\tif {}({}) {{", lookup, literal)?;
+            }
+            writeln!(stream, "\t\tt.Errorf(\"non-keyword %q accepted\", {})\n\t}}", literal)?;
+        }
+        writeln!(stream, "}}")?;
+        return Ok(());
+    }
+
+    fn write_java(&self, stream: &mut File) -> std::io::Result<()> {
+        let language: &Language_Output = self.language_output();
+        let class_name: String = pascal_case(&option_text(option.get_class_name()));
+        let lookup: String = camel_case(&option_text(option.get_function_name()));
+
+        writeln!(stream, "/* Tests generated by gperf.  Do not edit.  */\n")?;
+        writeln!(stream, "import java.util.Objects;\n")?;
+        writeln!(stream, "public final class {}Test {{\n", class_name)?;
+        writeln!(stream, "    private static int failures = 0;\n")?;
+        writeln!(stream, "    private static void check(boolean ok, String what, String s) {{\n        if (!ok) {{\n            System.err.println(what + \": \" + s);\n            failures++;\n        }}\n    }}\n")?;
+        writeln!(stream, "    public static void main(String[] args) {{")?;
+        if (option[TYPE]) {
+            writeln!(stream, "        {}.Entry e;", class_name)?;
+        }
+
+        for (i, (keyword, rest)) in self._keywords.iter().enumerate() {
+            let literal: String = language.string_literal(keyword);
+            if (!option[TYPE]) {
+                writeln!(stream, "        check({}.{}({}), \"keyword not found\", {});", class_name, lookup, literal, literal)?;
+                continue;
+            }
+            writeln!(stream, "        e = {}.{}({});", class_name, lookup, literal)?;
+            writeln!(stream, "        check(e != null, \"keyword not found\", {});", literal)?;
+            if (self._duplicate[i]) {
+                continue;
+            }
+            let mut conditions: Vec<String> = vec![format!("Objects.equals(e.{}, {})", camel_case(&self._fields[0]._name), literal)];
+            for (field, value) in self._fields[1..].iter().zip(language.payload(rest).iter()) {
+                conditions.push(match field._kind {
+                    Field_Kind::INTEGER | Field_Kind::FLOATING => format!("e.{} == {}", camel_case(&field._name), value),
+                    _ => format!("Objects.equals(e.{}, {})", camel_case(&field._name), value)
+                });
+            }
+            writeln!(stream, "        if (e != null) {{\n            check({}, \"keyword mapped to the wrong entry\", {});\n        }}",
+                     conditions.join("\n                  && "), literal)?;
+        }
+
+        for near_miss in self._near_misses.iter() {
+            let literal: String = language.string_literal(near_miss);
+            if (option[TYPE]) {
+                writeln!(stream, "        check({}.{}({}) == null, \"non-keyword accepted\", {});", class_name, lookup, literal, literal)?;
+            } else {
+                writeln!(stream, "        check(!{}.{}({}), \"non-keyword accepted\", {});", class_name, lookup, literal, literal)?;
+            }
+        }
+        writeln!(stream, "        System.exit(failures != 0 ? 1 : 0);\n    }}\n}}")?;
+        return Ok(());
+    }
+
+    fn write_python(&self, stream: &mut File) -> std::io::Result<()> {
+        let language: &Language_Output = self.language_output();
+        let lookup: String = option_text(option.get_function_name());
+
+        /* The generated code is imported as a module named after the
+           output file.  */
+        let output_file_name: String = option_text(option.get_output_file_name());
+        let base: &str = output_file_name.rsplit('/').next().unwrap_or(&output_file_name);
+        let module: &str = base.strip_suffix(".py").unwrap_or(base);
+
+        writeln!(stream, "# Tests generated by gperf.  Do not edit.\n\nimport unittest\n\nfrom {} import {}\n\n", module, lookup)?;
+        writeln!(stream, "class KeywordTest(unittest.TestCase):\n")?;
+
+        writeln!(stream, "    def test_keywords(self):")?;
+        for (i, (keyword, rest)) in self._keywords.iter().enumerate() {
+            let literal: String = language.string_literal(keyword);
+            if (!option[TYPE]) {
+                writeln!(stream, "        self.assertTrue({}({}))", lookup, literal)?;
+            } else if (self._duplicate[i]) {
+                writeln!(stream, "        self.assertIsNotNone({}({}))", lookup, literal)?;
+            } else {
+                let mut values: Vec<String> = vec![literal.clone()];
+                values.extend(language.payload(rest));
+                writeln!(stream, "        self.assertEqual({}({}), ({},))", lookup, literal, values.join(", "))?;
+            }
+        }
+
+        writeln!(stream, "\n    def test_non_keywords(self):")?;
+        for near_miss in self._near_misses.iter() {
+            writeln!(stream, "        self.{}({}({}))", if option[TYPE] { "assertIsNone" } else { "assertFalse" },
+                     lookup, language.string_literal(near_miss))?;
+        }
+        if (self._near_misses.is_empty()) {
+            writeln!(stream, "        pass")?;
+        }
+
+        writeln!(stream, "\n\nif __name__ == \"__main__\":\n    unittest.main()")?;
+        return Ok(());
+    }
+
+    /* Writes the tests to FILE_NAME, in the language selected with -L.  */
+    pub fn output(&self, file_name: *const char) {
+        let name: String = option_text(file_name);
+        let written: bool = match File::create(&name) {
+            Ok(mut stream) => {
+                if (option[GO]) {
+                    self.write_go(&mut stream).is_ok()
+                } else if (option[JAVA]) {
+                    self.write_java(&mut stream).is_ok()
+                } else if (option[PYTHON]) {
+                    self.write_python(&mut stream).is_ok()
+                } else {
+                    self.write_c(&mut stream, &name).is_ok()
+                }
+            }
+            Err(_) => false
+        };
+        if (!written) {
+            eprintln!("{}: cannot write tests {}\n", program_name, name);
+            process::exit(1);
+        }
+    }
+
+}
@@ -0,0 +1,202 @@
+mod keyword;
+mod keyword_list;
+mod options;
+mod positions;
+mod simulator;
+
+use std::ptr::null_mut;
+use std::collections::{BTreeSet, HashMap};
+
+/* Most keyword pairs listed for each key position.  */
+const MAX_PAIRS_SHOWN: usize = 3;
+
+/* Writes the --explain report to stderr: why the key positions were
+   chosen, the associated values, how each keyword's hash value is made
+   up, and what the search parameters amounted to.  */
+struct Explainer<'a> {
+
+    /* The keywords, duplicates excluded.  */
+    _keywords: Vec<*mut KeywordExt>,
+
+    /* Evaluates the hash function that was found.  */
+    _simulator: &'a Simulator
+}
+
+/* Shows a character the way it appears in C source.  */
+fn char_name(c: u32) -> String {
+    if (c >= 0x20 && c < 0x7f && c != '\'' as u32 && c != '\\' as u32) {
+        return format!("'{}'", char::from_u32(c).unwrap());
+    }
+    return format!("'\\{:03o}'", c);
+}
+
+/* Shows a key position in the syntax of the -k option.  */
+fn position_name(pos: i32) -> String {
+    if (pos == Positions::LASTCHAR) {
+        return String::from("$");
+    }
+    return (pos + 1).to_string();
+}
+
+impl<'a> Explainer<'a> {
+
+    pub fn new(head: *mut KeywordExt_List, simulator: &'a Simulator) -> Explainer<'a> {
+        let mut keywords: Vec<*mut KeywordExt> = Vec::new();
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            keywords.push((*temp).first());
+            temp = (*temp).rest();
+        }
+        Explainer { _keywords: keywords, _simulator: simulator }
+    }
+
+    fn allchars(keyword: *mut KeywordExt) -> &'a [u8] {
+        return &(*keyword).keyword._allchars;
+    }
+
+    /* What the hash function sees of a keyword when key position SKIP is
+       left out: its length, unless --no-length, and the multiset of the
+       selected characters.  */
+    fn signature(&self, keyword: *mut KeywordExt, skip: Option<i32>) -> (usize, Vec<u32>) {
+        let allchars: &[u8] = Explainer::allchars(keyword);
+        let mut chars: Vec<u32> = self._simulator.hash_terms(allchars).iter()
+                                      .filter(|&&(pos, _)| Some(pos) != skip)
+                                      .map(|&(_, c)| c)
+                                      .collect();
+        chars.sort();
+        return (if option[NOLENGTH] { 0 } else { allchars.len() }, chars);
+    }
+
+    /* Returns the keyword pairs that the hash function could not tell
+       apart without key position POS, and the number of such pairs.  */
+    fn forcing_pairs(&self, pos: i32) -> (Vec<(*mut KeywordExt, *mut KeywordExt)>, usize) {
+        let mut groups: HashMap<(usize, Vec<u32>), Vec<*mut KeywordExt>> = HashMap::new();
+        for &keyword in self._keywords.iter() {
+            groups.entry(self.signature(keyword, Some(pos))).or_insert(Vec::new()).push(keyword);
+        }
+
+        let mut pairs: Vec<(*mut KeywordExt, *mut KeywordExt)> = Vec::new();
+        let mut count: usize = 0;
+        for &keyword in self._keywords.iter() {
+            let group: &Vec<*mut KeywordExt> = &groups[&self.signature(keyword, Some(pos))];
+            for &other in group.iter() {
+                if (other == keyword) {
+                    /* Only list each pair once, in input order.  */
+                    break;
+                }
+                if (self.signature(other, None) != self.signature(keyword, None)) {
+                    if (pairs.len() < MAX_PAIRS_SHOWN) {
+                        pairs.push((other, keyword));
+                    }
+                    count += 1;
+                }
+            }
+        }
+        return (pairs, count);
+    }
+
+    fn report_positions(&self) {
+        let max_len: i32 = self._keywords.iter().map(|&k| (*k).keyword._allchars_length).max().unwrap_or(0);
+        let mut positions: BTreeSet<i32> = BTreeSet::new();
+        for &keyword in self._keywords.iter() {
+            for (pos, _) in self._simulator.hash_terms(Explainer::allchars(keyword)) {
+                positions.insert(pos);
+            }
+        }
+
+        eprint!("Key positions: {}", self._simulator.get_positions().to_string());
+        eprint!(" (longest keyword: {} characters)\n", max_len);
+
+        for &pos in positions.iter() {
+            let (pairs, count) = self.forcing_pairs(pos);
+            if (count == 0) {
+                eprint!("  position {}: not needed to separate any pair of keywords\n", position_name(pos));
+                continue;
+            }
+            eprint!("  position {}: needed to separate {} pair{}, e.g.\n",
+                    position_name(pos), count, if count == 1 { "" } else { "s" });
+            for (a, b) in pairs {
+                eprint!("    \"{}\" and \"{}\"\n",
+                        String::from_utf8_lossy(Explainer::allchars(a)),
+                        String::from_utf8_lossy(Explainer::allchars(b)));
+            }
+        }
+    }
+
+    fn report_asso_values(&self) {
+        let mut used: BTreeSet<u32> = BTreeSet::new();
+        for &keyword in self._keywords.iter() {
+            for (_, c) in self._simulator.hash_terms(Explainer::allchars(keyword)) {
+                used.insert(c);
+            }
+        }
+
+        eprint!("\nAssociated values of the {} characters at key positions:\n", used.len());
+        let asso_values: &Vec<i32> = self._simulator.get_asso_values();
+        for (i, &c) in used.iter().enumerate() {
+            eprint!("  {:>6} = {:<5}{}", char_name(c), asso_values[c as usize],
+                    if i % 4 == 3 || i + 1 == used.len() { "\n" } else { "" });
+        }
+    }
+
+    fn report_hash_values(&self) {
+        eprint!("\nHash values:\n");
+        for &keyword in self._keywords.iter() {
+            let allchars: &[u8] = Explainer::allchars(keyword);
+            let mut terms: Vec<String> = Vec::new();
+            if (!option[NOLENGTH]) {
+                terms.push(format!("{} (length)", allchars.len()));
+            }
+            for (pos, c) in self._simulator.hash_terms(allchars) {
+                terms.push(format!("{} ({} at {})", self._simulator.get_asso_values()[c as usize],
+                                   char_name(c), position_name(pos)));
+            }
+            eprint!("  \"{}\": {} = {}, word list index {}\n",
+                    String::from_utf8_lossy(allchars), terms.join(" + "),
+                    self._simulator.hash(allchars), (*keyword)._final_index);
+        }
+    }
+
+    /* Relates the search parameters to the result.  */
+    fn report_parameters(&self) {
+        let count: usize = self._keywords.len();
+        let max_hash_value: i32 = self._keywords.iter().map(|&k| (*k)._hash_value).max().unwrap_or(0);
+
+        /* The search rounds the range of associated values up to a power
+           of two.  */
+        let mut asso_value_max: u32 = (option.get_size_multiple() * count as f32) as u32;
+        asso_value_max |= asso_value_max >> 1;
+        asso_value_max |= asso_value_max >> 2;
+        asso_value_max |= asso_value_max >> 4;
+        asso_value_max |= asso_value_max >> 8;
+        asso_value_max |= asso_value_max >> 16;
+
+        eprint!("\nSearch parameters:\n");
+        eprint!("  size multiple {}: associated values were searched in 0..{} for {} keywords\n",
+                option.get_size_multiple(), asso_value_max, count);
+        if (option[RANDOM]) {
+            eprint!("  initial associated values: random\n");
+        } else {
+            eprint!("  initial associated value: {}\n", option.get_initial_asso_value());
+        }
+        if (option.get_jump() == 0) {
+            eprint!("  jump 0: associated values were changed randomly on collisions\n");
+        } else {
+            eprint!("  jump {}: associated values were increased by {} on collisions\n",
+                    option.get_jump(), option.get_jump());
+        }
+        eprint!("  result: maximum hash value {}, so {} of {} table slots are used ({:.0}%)\n",
+                max_hash_value, count, max_hash_value + 1,
+                100.0 * count as f64 / (max_hash_value + 1) as f64);
+    }
+
+    pub fn report(&self) {
+        eprint!("\nExplanation of the generated hash function\n\n");
+        self.report_positions();
+        self.report_asso_values();
+        self.report_hash_values();
+        self.report_parameters();
+        eprint!("\n");
+    }
+
+}
@@ -0,0 +1,248 @@
+mod keyword;
+mod keyword_list;
+mod options;
+
+use std::process;
+use std::ptr::null_mut;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/* Writes the header file requested with --output-header: the struct
+   declaration, the constants and the prototypes of the lookup and hash
+   functions, so that several translation units can share them.  The
+   implementation file then #includes the header instead of declaring
+   these itself, and the hash function is not static.  */
+struct Header_Output {
+
+    /* The struct declaration, or empty.  */
+    _struct_decl: String,
+
+    /* The return type of the lookup function.  */
+    _return_type: String,
+
+    _total_keys: i32,
+    _min_key_len: i32,
+    _max_key_len: i32,
+    _min_hash_value: i32,
+    _max_hash_value: i32
+}
+
+impl Header_Output {
+
+    pub fn new(head: *mut KeywordExt_List, struct_decl: &str, return_type: &str) -> Header_Output {
+        let mut result = Header_Output {
+            _struct_decl: if option[TYPE] && !option[NOTYPE] { String::from(struct_decl) } else { String::new() },
+            _return_type: if option[TYPE] {
+                String::from(return_type)
+            } else if option[KRC] {
+                String::from("char *")
+            } else {
+                String::from("const char *")
+            },
+            _total_keys: 0,
+            _min_key_len: i32::MAX,
+            _max_key_len: 0,
+            _min_hash_value: i32::MAX,
+            _max_hash_value: 0
+        };
+
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let mut keyword: *mut KeywordExt = (*temp).first();
+            while (keyword != null_mut()) {
+                let length: i32 = (*keyword).keyword._allchars_length;
+                result._total_keys += 1;
+                result._min_key_len = std::cmp::min(result._min_key_len, length);
+                result._max_key_len = std::cmp::max(result._max_key_len, length);
+                result._min_hash_value = std::cmp::min(result._min_hash_value, (*keyword)._hash_value);
+                result._max_hash_value = std::cmp::max(result._max_hash_value, (*keyword)._hash_value);
+                keyword = (*keyword)._duplicate_link;
+            }
+            temp = (*temp).rest();
+        }
+        return result;
+    }
+
+    /* Returns the include guard macro for FILE_NAME, e.g. SRC_KEYWORDS_H
+       for "src/keywords.h".  The directories are part of it, so that
+       headers of the same name in different directories can be included
+       together.  */
+    fn guard_name(file_name: &str) -> String {
+        let path: &str = file_name.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+        let mut guard: String = path.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        if (guard.chars().next().map_or(true, |c| c.is_ascii_digit())) {
+            guard.insert(0, '_');
+        }
+        return guard;
+    }
+
+    /* Returns the prefix of the constants: the class name in C++, else
+       the lookup function name, so that the headers of several keyword
+       sets can be included in one translation unit.  The implementation
+       file keeps its own unprefixed constants.  */
+    fn constant_prefix() -> String {
+        let name: String = option_text(if option[CPLUSPLUS] { option.get_class_name() } else { option.get_function_name() });
+        return name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    }
+
+    /* Returns the parameter list of the lookup and hash functions.  */
+    fn parameters() -> &'static str {
+        if (option[KRC] || option[C]) {
+            /* Old-style declarations: no prototype.  */
+            return "()";
+        }
+        return "(const char *str, unsigned int len)";
+    }
+
+    fn write_header(&self, stream: &mut File, file_name: &str) -> std::io::Result<()> {
+        let guard: String = Header_Output::guard_name(file_name);
+
+        writeln!(stream, "/* C{} header generated by {} */", if option[CPLUSPLUS] { "++" } else { "" }, program_name)?;
+        writeln!(stream, "\n#ifndef {}\n#define {}", guard, guard)?;
+
+        Header_Output::write_struct_decl(stream, &self._struct_decl)?;
+
+        let prefix: String = Header_Output::constant_prefix();
+        let constants: [(&str, i32); 5] = [
+            ("TOTAL_KEYWORDS", self._total_keys),
+            ("MIN_WORD_LENGTH", self._min_key_len),
+            ("MAX_WORD_LENGTH", self._max_key_len),
+            ("MIN_HASH_VALUE", self._min_hash_value),
+            ("MAX_HASH_VALUE", self._max_hash_value)
+        ];
+        if (option[ENUM]) {
+            writeln!(stream, "\nenum\n  {{")?;
+            for (i, (name, value)) in constants.iter().enumerate() {
+                writeln!(stream, "    {}_{} = {}{}", prefix, name, value, if i + 1 < constants.len() { "," } else { "" })?;
+            }
+            writeln!(stream, "  }};")?;
+        } else {
+            writeln!(stream, "")?;
+            for (name, value) in constants.iter() {
+                writeln!(stream, "#define {}_{} {}", prefix, name, value)?;
+            }
+        }
+
+        Header_Output::write_prototypes(stream, &self._return_type)?;
+
+        writeln!(stream, "\n#endif /* {} */", guard)?;
+        return Ok(());
+    }
+
+    /* Writes STRUCT_DECL, if not empty, as a complete declaration.  */
+    pub fn write_struct_decl(stream: &mut File, struct_decl: &str) -> std::io::Result<()> {
+        if (!struct_decl.is_empty()) {
+            writeln!(stream, "\n{}", struct_decl.trim_end())?;
+            if (!struct_decl.trim_end().ends_with(';')) {
+                writeln!(stream, ";")?;
+            }
+        }
+        return Ok(());
+    }
+
+    /* Writes the prototypes of the lookup and hash functions, or the class
+       declaration in C++, with RETURN_TYPE for the lookup function.  */
+    pub fn write_prototypes(stream: &mut File, return_type: &str) -> std::io::Result<()> {
+        let function_name: String = option_text(option.get_function_name());
+        let hash_name: String = option_text(option.get_hash_name());
+
+        if (option[CPLUSPLUS]) {
+            writeln!(stream, "\nclass {}\n{{\nprivate:\n  static inline unsigned int {} {};\npublic:\n  static {}{} {};\n}};",
+                     option_text(option.get_class_name()), hash_name, Header_Output::parameters(),
+                     return_type, function_name, Header_Output::parameters())?;
+        } else {
+            writeln!(stream, "\n#ifdef __cplusplus\nextern \"C\" {{\n#endif")?;
+            writeln!(stream, "extern unsigned int {} {};", hash_name, Header_Output::parameters())?;
+            writeln!(stream, "extern {}{} {};", return_type, function_name, Header_Output::parameters())?;
+            writeln!(stream, "#ifdef __cplusplus\n}}\n#endif")?;
+        }
+        return Ok(());
+    }
+
+    /* Writes the header to FILE_NAME.  */
+    pub fn output(&self, file_name: *const char) {
+        let name: String = option_text(file_name);
+        let written: bool = match File::create(&name) {
+            Ok(mut stream) => self.write_header(&mut stream, &name).is_ok(),
+            Err(_) => false
+        };
+        if (!written) {
+            eprintln!("{}: cannot write header {}\n", program_name, name);
+            process::exit(1);
+        }
+    }
+
+    /* Returns the name under which a file written to INCLUDING_FILE must
+       #include the header HEADER: the compiler looks up quoted includes in
+       the directory of the including file, not in the current directory.
+       The path is computed from the names alone, as the files need not
+       exist yet; when that is not possible, e.g. when the directory of
+       INCLUDING_FILE goes up with "..", only the base name is used.  */
+    pub fn include_path(header: &str, including_file: &str) -> String {
+        let header_path: &Path = Path::new(header);
+        if (including_file.is_empty() || including_file == "-") {
+            /* Standard output: the directory is unknown.  */
+            return String::from(header);
+        }
+        let base_name: String = header_path.file_name()
+            .map_or(String::from(header), |name| name.to_string_lossy().into_owned());
+        if (header_path.is_absolute() != Path::new(including_file).is_absolute()) {
+            return base_name;
+        }
+
+        let from: Vec<Component> = Path::new(including_file).parent()
+            .map_or(Vec::new(), |dir| dir.components().filter(|c| *c != Component::CurDir).collect());
+        let to: Vec<Component> = header_path.components().filter(|c| *c != Component::CurDir).collect();
+        let mut common: usize = 0;
+        while (common < from.len() && common + 1 < to.len() && from[common] == to[common]) {
+            common += 1;
+        }
+        if (from[common..].iter().any(|c| !matches!(c, Component::Normal(_)))) {
+            return base_name;
+        }
+        let mut path: PathBuf = PathBuf::new();
+        for _ in common..from.len() {
+            path.push("..");
+        }
+        for component in to[common..].iter() {
+            path.push(component.as_os_str());
+        }
+        return path.to_string_lossy().into_owned();
+    }
+
+    /* Writes the #include of the header FILE_NAME, which replaces the
+       declarations in the implementation file.  */
+    pub fn output_include(&self, file_name: *const char) {
+        let including_file: String =
+            if option.get_output_file_name() != null_mut() { option_text(option.get_output_file_name()) } else { String::new() };
+        print!("#include \"{}\"\n", Header_Output::include_path(&option_text(file_name), &including_file));
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn include_path_is_relative_to_the_including_file() {
+        assert_eq!(Header_Output::include_path("src/kw.h", "src/kw.c"), "kw.h");
+        assert_eq!(Header_Output::include_path("./src/kw.h", "src/kw.c"), "kw.h");
+        assert_eq!(Header_Output::include_path("include/kw.h", "src/kw.c"), "../include/kw.h");
+        assert_eq!(Header_Output::include_path("kw.h", "out/kw.c"), "../kw.h");
+        assert_eq!(Header_Output::include_path("include/kw.h", "kw.c"), "include/kw.h");
+        assert_eq!(Header_Output::include_path("/usr/include/kw.h", "/usr/src/kw.c"), "../include/kw.h");
+    }
+
+    #[test]
+    fn include_path_falls_back_to_the_base_name() {
+        assert_eq!(Header_Output::include_path("include/kw.h", "../src/kw.c"), "kw.h");
+        assert_eq!(Header_Output::include_path("/usr/include/kw.h", "src/kw.c"), "kw.h");
+        assert_eq!(Header_Output::include_path("include/kw.h", "-"), "include/kw.h");
+    }
+
+}
@@ -1,1020 +1,2000 @@
+mod char_equiv;
 mod keyword_list;
 mod options;
+mod struct_fields;
 
-use libc::c_char;
 use std::process;
-use std::ptr::{null, null_mut, copy_nonoverlapping};
+use std::ptr::{null, null_mut};
 use std::fs::File;
-use std::ffi::Cstr;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::collections::HashMap;
 
 struct Input{
 
-    _stream: File,
+    /* The input files, in command-line order.  */
+    _streams: Vec<File>,
     _factory: *mut Keyword_Factory,
 
-    pub _input: *mut char,
-    pub _input_end: *mut char,
-
-    pub _verbatim_declarations: *const char,
-    pub _verbatim_declarations_end: *const char,
+    /* The contents of the %{ ... %} sections, without the %{ and %}.  */
+    pub _verbatim_declarations: Vec<u8>,
     pub _verbatim_declarations_lineno: u32,
 
-    pub _verbatim_code: *const char,
-    pub _verbatim_code_end: *const char,
+    /* Where each file's %{ ... %} section starts, for #line.  */
+    pub _verbatim_declarations_sources: Vec<Source_Location>,
+
+    /* The contents of the trailing code sections, after the second %%.  */
+    pub _verbatim_code: Vec<u8>,
     pub _verbatim_code_lineno: u32,
 
-    pub _struct_decl: *const char,
+    /* Where each file's trailing code section starts, for #line.  */
+    pub _verbatim_code_sources: Vec<Source_Location>,
+
+    pub _struct_decl: String,
+    pub _struct_decl_file_name: *const char,
+    pub _struct_decl_lineno: u32,
+
+    /* The fields of the struct declaration, if it has a body.  */
+    pub _struct_fields: Vec<Struct_Field>,
+
+    pub _return_type: String,
+
+    pub _struct_tag: String,
+
+    pub _head: *mut Keyword_List,
+
+    /* Where the next keyword is appended.  */
+    _list_tail: *mut *mut Keyword_List,
+
+    /* The keyword sets introduced by %set, in order of first mention.
+       Keywords outside of any set are in _head.  */
+    pub _sets: Vec<Box<Keyword_Set>>,
+
+    /* The set whose keywords are being read, if any, and where the
+       keywords outside of any set continue while it is.  */
+    _current_set: Option<usize>,
+    _default_tail: *mut *mut Keyword_List,
+
+    pub _charset_dependent: bool,
+
+    /* Whether the warning about record fields ignored without
+       --struct-type was given.  */
+    _fields_ignored: bool
+
+}
+
+/* A field of a CSV, TSV or JSON record, other than the keyword.  */
+struct Record_Field {
+
+    /* The member name, for JSON objects.  */
+    _name: Option<Vec<u8>>,
+
+    _value: Vec<u8>,
+
+    /* Whether the value is text, i.e. a CSV or TSV field or a JSON
+       string, rather than a JSON number, true, false or null.  Text
+       initializes string fields as a C string literal.  */
+    _text: bool
+}
+
+/* The start of a piece of a verbatim section: its byte offset in the
+   section text and where it comes from in the input.  */
+struct Source_Location {
+    pub _offset: usize,
+    pub _file_name: *const char,
+    pub _lineno: u32
+}
+
+/* The section structure of an input file, found by a first pass.  */
+struct Input_Layout {
+
+    /* Line numbers of the first two "%%" separator lines.  */
+    _separator_lineno: [u32; 2],
+    _separators: usize,
+
+    /* Whether a line before the first separator starts with '%'.  */
+    _percent_before_separator: bool,
+
+    /* Number of lines in the input.  */
+    _lines: u32
+}
+
+/* State carried across the lines of a declarations section.  */
+struct Declarations_State {
+    _verbatim_open: bool,
+    _verbatim_seen: bool,
+    _struct_decl: Vec<u8>,
+    _struct_decl_linenos: Vec<u32>,
+
+    /* The set whose declarations follow a %set line, if any.  */
+    _set: Option<usize>
+}
+
+impl Declarations_State {
+    fn new() -> Declarations_State {
+        Declarations_State {
+            _verbatim_open: false,
+            _verbatim_seen: false,
+            _struct_decl: Vec::new(),
+            _struct_decl_linenos: Vec::new(),
+            _set: None
+        }
+    }
+}
+
+/* A named keyword set.  "%set NAME" in the declarations section starts
+   the declarations of the set: its struct declaration, %struct-type and
+   the lookup-function-name, word-array-name and hash-function-name
+   %defines.  In the keywords section it starts the keywords of the set.
+   Each set gets its own lookup function and tables in the one output.  */
+struct Keyword_Set {
+    pub _name: String,
+
+    /* Where the set was first mentioned.  */
+    pub _file_name: *const char,
+    pub _lineno: u32,
+
+    /* The names given with %define, or null for the global name with
+       "_NAME" appended.  */
+    pub _function_name: *const char,
+    pub _wordlist_name: *const char,
+    pub _hash_name: *const char,
+
+    /* Whether the set has %struct-type, and its struct declaration.  */
+    pub _type: bool,
+    _struct_decl_text: Vec<u8>,
+    _struct_decl_linenos: Vec<u32>,
+    pub _struct_decl: String,
     pub _struct_decl_lineno: u32,
-    
-    pub _return_type: *const char,
-    
-    pub _struct_tag: *const char,
+    pub _struct_fields: Vec<Struct_Field>,
+    pub _return_type: String,
+    pub _struct_tag: String,
 
     pub _head: *mut Keyword_List,
+    _list_tail: *mut *mut Keyword_List
+}
 
-    pub _charset_dependent: bool
+impl Keyword_Set {
+
+    /* Returns NAME if not null, else DEFAULT_NAME with "_" and the set
+       name appended.  */
+    fn set_name(&self, name: *const char, default_name: *const char) -> *const char {
+        if (name != null()) {
+            return name;
+        }
+        let mut result: Vec<u8> = option_string(default_name);
+        result.push(b'_');
+        result.extend_from_slice(self._name.as_bytes());
+        return option_arg(&result);
+    }
+
+    /* Switches the options to the names and struct type of this set, for
+       its output.  FUNCTION_NAME, WORDLIST_NAME and HASH_NAME are the
+       global names, saved before the first set is selected.  */
+    pub fn select(&self, function_name: *const char, wordlist_name: *const char, hash_name: *const char) {
+        option.select_keyword_set(self.set_name(self._function_name, function_name),
+                                  self.set_name(self._wordlist_name, wordlist_name),
+                                  self.set_name(self._hash_name, hash_name),
+                                  self._type);
+    }
 
 }
 
+/* Name of the file currently being read, for diagnostics.  */
+static mut current_file_name: *const char = null();
+
+/* Limit on nested %include directives, to catch cycles.  */
+const MAX_INCLUDE_DEPTH: u32 = 32;
+
 impl Input {
- 
-    //check
-    pub fn new(&mut self, stream: FILE, keyword_factory: *mut Keyword_Factory) -> Input {
 
-        self._stream = stream;
-        self._factory = keyword_factory;
+    pub fn new(streams: Vec<File>, keyword_factory: *mut Keyword_Factory) -> Input {
+        Input {
+            _streams: streams,
+            _factory: keyword_factory,
+            _verbatim_declarations: Vec::new(),
+            _verbatim_declarations_lineno: 0,
+            _verbatim_declarations_sources: Vec::new(),
+            _verbatim_code: Vec::new(),
+            _verbatim_code_lineno: 0,
+            _verbatim_code_sources: Vec::new(),
+            _struct_decl: String::new(),
+            _struct_decl_file_name: null(),
+            _struct_decl_lineno: 0,
+            _struct_fields: Vec::new(),
+            _return_type: String::new(),
+            _struct_tag: String::new(),
+            _head: null_mut(),
+            _list_tail: null_mut(),
+            _sets: Vec::new(),
+            _current_set: None,
+            _default_tail: null_mut(),
+            _charset_dependent: false,
+            _fields_ignored: false
+        }
+    }
+
+    /* Reads all input files.  The keyword lists are concatenated in
+       command-line order.  */
+    pub fn read_input(&mut self) {
+
+        self._list_tail = &mut self._head;
+        let mut struct_decl: Option<(Vec<u8>, Vec<u32>, *const char)> = None;
+        let mut charset_dependent: bool = false;
+
+        if (option.get_input_format() != InputFormat::GPERF && option[TYPE]) {
+            /* The records are mapped to the struct fields as they are read,
+               so the declaration is needed first.  */
+            struct_decl = Some(Input::read_struct_declaration_file());
+            let (decl, linenos, file_name) = struct_decl.clone().unwrap();
+            current_file_name = file_name;
+            self._struct_decl_file_name = file_name;
+            self.set_struct_decl(decl, linenos);
+            self._struct_fields = parse_struct_fields(&self._struct_decl, self._struct_decl_file_name,
+                                                      self._struct_decl_lineno);
+        }
+
+        let streams: Vec<File> = std::mem::take(&mut self._streams);
+        for (i, stream) in streams.into_iter().enumerate() {
+            current_file_name = if i < option.get_input_file_names().len() {
+                option.get_input_file_names()[i]
+            } else {
+                null()
+            };
+
+            /* Each file starts outside of any set.  */
+            self.select_set(None);
+
+            let state: Declarations_State = match option.get_input_format() {
+                InputFormat::GPERF => self.read_file(stream, &mut charset_dependent),
+                InputFormat::CSV => {
+                    self.read_delimited_file(stream, b',', &mut charset_dependent);
+                    Declarations_State::new()
+                }
+                InputFormat::TSV => {
+                    self.read_delimited_file(stream, b'\t', &mut charset_dependent);
+                    Declarations_State::new()
+                }
+                InputFormat::JSON => {
+                    self.read_json_file(stream, &mut charset_dependent);
+                    Declarations_State::new()
+                }
+            };
+
+            /* Only one struct declaration is allowed, but a file may repeat
+               the one given in an earlier file.  */
+            if (state._struct_decl.iter().any(|&c| !(c == b' ' || c == b'\t' || c == b'\n'))) {
+                match struct_decl {
+                    None => {
+                        struct_decl = Some((state._struct_decl, state._struct_decl_linenos, current_file_name));
+                    }
+                    Some((ref decl, ref linenos, file_name)) => {
+                        if (*decl != state._struct_decl) {
+                            eprintln!("{}:{}: struct declaration differs from the one in {}:{}\n",
+                                      pretty_input_file_name(), state._struct_decl_linenos[0],
+                                      file_name, linenos[0]);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+
+        if (option[TYPE]) {
+            let (decl, linenos, file_name) = struct_decl.unwrap_or((Vec::new(), Vec::new(), current_file_name));
+            current_file_name = file_name;
+            self._struct_decl_file_name = file_name;
+            self.set_struct_decl(decl, linenos);
+        }
+
+        self.select_set(None);
+        *self._list_tail = null_mut();
+        for set in self._sets.iter_mut() {
+            *set._list_tail = null_mut();
+        }
 
+        if (option[SHAREDLIB] && !self._sets.is_empty()) {
+            /* Each set would need its own string pool, or the offsets into
+               one shared pool; neither is implemented.  */
+            let set: &Keyword_Set = &self._sets[0];
+            eprintln!("{}:{}: %set cannot be combined with %pic or --pic\n",
+                      option_text(set._file_name), set._lineno);
+            process::exit(1);
+        }
+
+        if (option[TYPE]) {
+            /* Check each keyword's initializers against the struct fields.  */
+            if (option.get_input_format() == InputFormat::GPERF) {
+                self._struct_fields = parse_struct_fields(&self._struct_decl, self._struct_decl_file_name,
+                                                          self._struct_decl_lineno);
+            }
+            let mut temp: *mut Keyword_List = self._head;
+            while (temp != null_mut()) {
+                check_initializers(&self._struct_fields, &mut *(*temp).first());
+                temp = (*temp).rest();
+            }
+        }
+
+        for i in 0..self._sets.len() {
+            self.finish_set(i);
+        }
+
+        if (option[UPPERLOWER] || has_char_equivs()) {
+            Input::check_case_duplicates(&mut self._head);
+            for set in self._sets.iter_mut() {
+                Input::check_case_duplicates(&mut set._head);
+            }
+        }
+
+        if (self._head == null_mut() && self._sets.iter().all(|set| set._head == null_mut())) {
+            eprintln!("{}: No keywords in input file!\n",
+            pretty_input_file_name());
+        }
+        self._charset_dependent = charset_dependent;
     }
 
-    pub fn read_input() {
-    
-        let mut input: *mut char = null_mut();
-        let mut input_size: usize = 0;
+    /* Diagnoses keywords that differ from an earlier one only by case or
+       by equivalent characters, which the comparison cannot tell apart.  With
+       --canonical-index the first spelling is the canonical one and the
+       later ones are dropped with a warning; otherwise they are an error.
+       Exact duplicates are left to the search.  */
+    fn check_case_duplicates(head: &mut *mut Keyword_List) {
+        let mut seen: HashMap<Vec<u8>, *mut Keyword> = HashMap::new();
+        let mut errors: bool = false;
+        let map: Vec<u8> = char_fold_map();
+
+        let mut link: *mut *mut Keyword_List = head;
+        while (*link != null_mut()) {
+            let keyword: *mut Keyword = (**link).first();
+            let folded: Vec<u8> = fold(&(*keyword)._allchars, &map);
+            match seen.get(&folded) {
+                Some(&first) if (*first)._allchars != (*keyword)._allchars => {
+                    eprintln!("{}:{}: {}keyword \"{}\" differs only by {} from\n{}:{}: keyword \"{}\"\n",
+                              (*keyword)._file_name, (*keyword)._lineno,
+                              if option[CANONICAL] { "warning: " } else { "" },
+                              String::from_utf8_lossy(&(*keyword)._allchars),
+                              if (*first)._allchars.eq_ignore_ascii_case(&(*keyword)._allchars) { "case" } else { "equivalent characters" },
+                              (*first)._file_name, (*first)._lineno,
+                              String::from_utf8_lossy(&(*first)._allchars));
+                    if (option[CANONICAL]) {
+                        *link = (**link)._cdr;
+                        continue;
+                    }
+                    errors = true;
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(folded, keyword);
+                }
+            }
+            link = &mut (**link)._cdr;
+        }
+
+        if (errors) {
+            process::exit(1);
+        }
+    }
 
-        let mut content_string = String::new();
-        _stream.read_to_string(&mut content_string).expect("{}: error while reading input file\n", pretty_input_file_name());
-        let mut content_vector: Vec<char> = content_string.chars().collect();
+    /* Reads one input file in two passes over its lines: the first finds
+       the sections, the second parses them.  Only the verbatim sections
+       and the keywords are kept, never the whole file.  */
+    fn read_file(&mut self, stream: File, charset_dependent: &mut bool) -> Declarations_State {
 
-        input_length = content_string.len() - 1;
+        let mut stream: BufReader<File> = make_seekable(BufReader::new(stream));
+        let layout: Input_Layout = scan_layout(&mut stream);
 
-        if (input_length < 0) {
+        if (layout._lines == 0) {
             eprintln!("{}: The input file is empty!\n",
             pretty_input_file_name ());
             std::process::exit(1);
         }
 
-        input = &mut content_vector[0] as *mut char;
-
-        /* We use input_end as a limit, in order to cope with NUL bytes in the
-        input.  But note that one trailing NUL byte has been added after
-        input_end, for convenience.  */
-        let mut input_end: *mut char = input.offset(input_length);
-        
-        let mut declarations: *const char;
-        let mut declarations_end: *const char;
-        let mut keywords: *const char;
-        let mut keywords_end: *const char;
-        let mut keywords_lineno: u32;
-
-        {
-
-            let mut separator: [*const char; 2] = [null_mut(), null_mut()];
-            let mut separato_linno: [u32; 2] = [0, 0];
-            let mut separators: i32 = 0;
-            {
-                let lineno: u32 = 1;
-                let mut p: *const char = input;
-                while p < input_end {
-                    if p[0] == '0' && p[1] == '%' {
-                        separator[separators] = p;
-                        separator_lineno[separators] = lineno;
-                        separators += 1;
-                        if(separators == 2) {
-                            break;
+        /* Determine the sections.  Line numbers are 1-based; a section
+           extends up to, but excluding, its end line.  */
+        let has_declarations: bool;
+        if layout._separators == 1 {
+            has_declarations = option[TYPE] || layout._percent_before_separator;
+        } else {
+            has_declarations = (layout._separators > 0);
+        }
+
+        let declarations_end_lineno: u32 = if has_declarations { layout._separator_lineno[0] } else { 0 };
+        let keywords_lineno: u32 = if has_declarations { layout._separator_lineno[0] + 1 } else { 1 };
+        let keywords_end_lineno: u32;
+        if (layout._separators > (if has_declarations {1} else {0})) {
+            keywords_end_lineno = layout._separator_lineno[layout._separators-1];
+            if (self._verbatim_code_lineno == 0) {
+                self._verbatim_code_lineno = keywords_end_lineno;
+            }
+        } else {
+            keywords_end_lineno = layout._lines + 1;
+        }
+
+        let mut declarations = Declarations_State::new();
+
+        let delimiters: Vec<u8> = option_string(option.get_delimiters());
+
+        /* Dispatch each line to its section.  */
+        let mut line: Vec<u8> = Vec::new();
+        let mut lineno: u32 = 0;
+        while read_line(&mut stream, &mut line) {
+            lineno += 1;
+
+            if (lineno < declarations_end_lineno) {
+                self.parse_declaration_line(&line, lineno, &mut declarations);
+            } else if (lineno == declarations_end_lineno) {
+                /* Give a warning if the separator line is nonempty.  */
+                if (line[2..].iter().any(|&c| !(c == b' ' || c == b'\t' || c == b'\n'))) {
+                    eprintln!("{}:{}: warning: junk after %% is ignored\n",
+                            pretty_input_file_name(), lineno);
+                }
+            } else if (lineno >= keywords_lineno && lineno < keywords_end_lineno) {
+                self.parse_keywords_line(&line, lineno, &delimiters, charset_dependent, 0);
+            } else if (lineno == keywords_end_lineno) {
+                self._verbatim_code_sources.push(Source_Location {
+                    _offset: self._verbatim_code.len(),
+                    _file_name: current_file_name,
+                    _lineno: lineno
+                });
+                self._verbatim_code.extend_from_slice(&line[2..]);
+            } else {
+                self._verbatim_code.extend_from_slice(&line);
+            }
+        }
+
+        if (declarations._verbatim_open) {
+                eprintln! ("{}:{}: unterminated %{{ section\n",
+                    pretty_input_file_name (), self._verbatim_declarations_lineno);
+                std::process::exit(1);
+        }
+
+        return declarations;
+    }
+
+    /* Handles one line of a keywords section: a comment, an %include
+       directive or a keyword.  */
+    fn parse_keywords_line(&mut self, line: &[u8], lineno: u32, delimiters: &[u8],
+                           charset_dependent: &mut bool, depth: u32) {
+
+        let mut file_name: Vec<u8> = Vec::new();
+        if (is_include_declaration (line, lineno, &mut file_name)) {
+            self.include_keywords(&file_name, lineno, delimiters, charset_dependent, depth + 1);
+            return;
+        }
+
+        let mut set_name: Vec<u8> = Vec::new();
+        if (is_set_declaration (line, lineno, &mut set_name)) {
+            let set: usize = self.find_set(&set_name, lineno);
+            self.select_set(Some(set));
+            return;
+        }
+
+        let new_kw: *mut Keyword = self.parse_keyword_line(line, lineno, delimiters, charset_dependent);
+        if (new_kw != null_mut()) {
+            self.append_keyword(new_kw);
+        }
+    }
+
+    /* Adds a keyword at the end of the list.  */
+    fn append_keyword(&mut self, new_kw: *mut Keyword) {
+        *self._list_tail = Box::into_raw(Box::new(Keyword_List::new(new_kw)));
+        self._list_tail = &mut (**self._list_tail)._cdr;
+    }
+
+    /* Allocates a keyword read from a CSV, TSV or JSON file and adds it
+       to the list.  The other fields, separated by commas, form the rest
+       of the struct initializer.  */
+    fn append_record(&mut self, keyword: Vec<u8>, fields: &Vec<Record_Field>, lineno: u32,
+                     charset_dependent: &mut bool) {
+
+        if (keyword.len() > 0) {
+            *charset_dependent = true;
+        }
+
+        if (!option[TYPE] && !fields.is_empty() && !self._fields_ignored) {
+            eprintln!("{}:{}: warning: fields other than the keyword are ignored without --struct-type\n",
+                      pretty_input_file_name(), lineno);
+            self._fields_ignored = true;
+        }
+
+        let rest: Vec<u8> = if option[TYPE] { self.record_initializers(fields, lineno) } else { Vec::new() };
+        let new_kw: *mut Keyword = (*self._factory).create_keyword (keyword, rest);
+        (*new_kw)._file_name = current_file_name;
+        (*new_kw)._lineno = lineno;
+        self.append_keyword(new_kw);
+    }
+
+    /* Returns the struct initializers for the FIELDS of a record: JSON
+       object members by the name of the struct field they initialize,
+       other records in order.  Text becomes a C string literal for string
+       fields and is taken as C source otherwise, e.g. for enumerators.  */
+    fn record_initializers(&self, fields: &Vec<Record_Field>, lineno: u32) -> Vec<u8> {
+
+        let struct_fields: &[Struct_Field] = if self._struct_fields.is_empty() { &[] } else { &self._struct_fields[1..] };
+        let mut values: Vec<&Record_Field> = Vec::new();
+        if (fields.iter().any(|f| f._name.is_some())) {
+            for field in fields.iter() {
+                let name: &Vec<u8> = field._name.as_ref().unwrap();
+                if (!struct_fields.iter().any(|s| s._name.as_bytes() == &name[..])) {
+                    eprintln!("{}:{}: member \"{}\" is not a field of the struct\n",
+                              pretty_input_file_name(), lineno, String::from_utf8_lossy(name));
+                    std::process::exit(1);
+                }
+            }
+            for struct_field in struct_fields.iter() {
+                match fields.iter().find(|f| f._name.as_ref().unwrap() == struct_field._name.as_bytes()) {
+                    Some(field) => values.push(field),
+                    None => {
+                        eprintln!("{}:{}: object has no member \"{}\"\n",
+                                  pretty_input_file_name(), lineno, struct_field._name);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        } else {
+            values = fields.iter().collect();
+        }
+
+        let mut initializers: Vec<Vec<u8>> = Vec::new();
+        for (i, field) in values.iter().enumerate() {
+            let is_string: bool = struct_fields.get(i).map_or(false, |s| s._kind == Field_Kind::STRING);
+            initializers.push(if field._text && is_string { c_string_literal(&field._value) } else { field._value.clone() });
+        }
+        return initializers.join(&b", "[..]);
+    }
+
+    /* Reads the file given with --struct-declaration.  Returns the
+       declaration, the line number of each of its lines and the file
+       name.  */
+    fn read_struct_declaration_file() -> (Vec<u8>, Vec<u32>, *const char) {
+
+        let file_name: *const char = option.get_struct_declaration_file_name();
+        if (file_name == null()) {
+            eprintln!("{}: --struct-type with this input format needs --struct-declaration=FILE\n",
+                      program_name);
+            std::process::exit(1);
+        }
+
+        let mut decl: Vec<u8> = Vec::new();
+        let read: bool = match File::open(String::from_utf8_lossy(&option_string(file_name)).into_owned()) {
+            Ok(mut stream) => stream.read_to_end(&mut decl).is_ok(),
+            Err(_) => false
+        };
+        if (!read) {
+            eprintln!("{}: cannot read struct declaration file {}\n", program_name, file_name);
+            std::process::exit(1);
+        }
+
+        let lines: u32 = decl.iter().filter(|&&c| c == b'\n').count() as u32 + 1;
+        return (decl, (1..=lines).collect(), file_name);
+    }
+
+    /* Reads a CSV file (with SEPARATOR ',') or a TSV file (with SEPARATOR
+       '\t').  Each record yields one keyword.  */
+    fn read_delimited_file(&mut self, stream: File, separator: u8, charset_dependent: &mut bool) {
+
+        let mut stream: BufReader<File> = BufReader::new(stream);
+        let key_column: usize = key_field_column();
+        let mut fields: Vec<Vec<u8>> = Vec::new();
+        let mut lineno: u32 = 0;
+
+        loop {
+            let record_lineno: u32 = lineno + 1;
+            if (!read_record(&mut stream, separator, separator == b',', &mut lineno, &mut fields)) {
+                break;
+            }
+
+            if (fields.len() == 1 && fields[0].is_empty()) {
+                /* Empty line.  */
+                continue;
+            }
+
+            if (key_column >= fields.len()) {
+                eprintln!("{}:{}: record has {} fields, but the keyword is field {}\n",
+                          pretty_input_file_name(), record_lineno, fields.len(), key_column + 1);
+                std::process::exit(1);
+            }
+
+            let keyword: Vec<u8> = fields.remove(key_column);
+            let record: Vec<Record_Field> = fields.drain(..)
+                .map(|value| Record_Field { _name: None, _value: value, _text: true })
+                .collect();
+            self.append_record(keyword, &record, record_lineno, charset_dependent);
+        }
+    }
+
+    /* Reads a JSON file: an array whose elements are objects or arrays,
+       one per keyword.  */
+    fn read_json_file(&mut self, stream: File, charset_dependent: &mut bool) {
+
+        let mut reader: Json_Reader = Json_Reader::new(stream, option_text(pretty_input_file_name()));
+
+        reader.skip_whitespace();
+        reader.expect(b'[');
+        reader.skip_whitespace();
+        if (reader.peek() == Some(b']')) {
+            reader.next();
+        } else {
+            loop {
+                reader.skip_whitespace();
+                let element_lineno: u32 = reader._lineno;
+                let (keyword, fields) = match reader.peek() {
+                    Some(b'{') => reader.parse_object_record(),
+                    Some(b'[') => reader.parse_array_record(),
+                    _ => reader.error("expected an object or an array")
+                };
+                self.append_record(keyword, &fields, element_lineno, charset_dependent);
+
+                reader.skip_whitespace();
+                match reader.next() {
+                    Some(b',') => continue,
+                    Some(b']') => break,
+                    _ => reader.error("expected ',' or ']'")
+                }
+            }
+        }
+
+        reader.skip_whitespace();
+        if (reader.peek() != None) {
+            reader.error("junk after JSON array");
+        }
+    }
+
+    /* Reads the keyword lines of an %include'd file.  A relative file name
+       is taken relative to the directory of the including file.  */
+    fn include_keywords(&mut self, file_name: &[u8], lineno: u32, delimiters: &[u8],
+                        charset_dependent: &mut bool, depth: u32) {
+
+        if (depth > MAX_INCLUDE_DEPTH) {
+            eprintln!("{}:{}: %include nested too deeply\n",
+                      pretty_input_file_name(), lineno);
+            std::process::exit(1);
+        }
+
+        let mut path: PathBuf = PathBuf::from(String::from_utf8_lossy(file_name).into_owned());
+        if (path.is_relative() && current_file_name != null()) {
+            let including: PathBuf = PathBuf::from(String::from_utf8_lossy(&option_string(current_file_name)).into_owned());
+            if let Some(dir) = including.parent() {
+                path = dir.join(path);
+            }
+        }
+
+        let mut stream: BufReader<File> = match File::open(&path) {
+            Ok(file) => BufReader::new(file),
+            Err(_) => {
+                eprintln!("{}:{}: cannot open included file {}\n",
+                          pretty_input_file_name(), lineno, path.display());
+                std::process::exit(1);
+            }
+        };
+
+        let including_file_name: *const char = current_file_name;
+        current_file_name = option_arg(path.to_string_lossy().as_bytes());
+
+        let mut line: Vec<u8> = Vec::new();
+        let mut included_lineno: u32 = 0;
+        while read_line(&mut stream, &mut line) {
+            included_lineno += 1;
+            self.parse_keywords_line(&line, included_lineno, delimiters, charset_dependent, depth);
+        }
+
+        current_file_name = including_file_name;
+    }
+
+    /* Handles one line of the declarations section.  */
+    fn parse_declaration_line(&mut self, line: &[u8], lineno: u32, state: &mut Declarations_State) {
+
+        let mut set_name: Vec<u8> = Vec::new();
+
+        if (line[0] == b'%') {
+            if (line.len() > 1 && line[1] == b'{') {
+                /* Handle %{.  */
+                if (state._verbatim_seen) {
+                    eprintln!("{}:{}:\n{}:{}: only one %{{...%}} section is allowed\n",
+                    pretty_input_file_name (),
+                    self._verbatim_declarations_lineno,
+                    pretty_input_file_name (), lineno);
+                    std::process::exit(1);
+                }
+                state._verbatim_seen = true;
+                state._verbatim_open = true;
+                self._verbatim_declarations_sources.push(Source_Location {
+                    _offset: self._verbatim_declarations.len(),
+                    _file_name: current_file_name,
+                    _lineno: lineno
+                });
+                self._verbatim_declarations.extend_from_slice(&line[2..]);
+                self._verbatim_declarations_lineno = lineno;
+            } else if (line.len() > 1 && line[1] == b'}') {
+                if (!state._verbatim_seen) {
+                  eprintln!("{}:{}: %}} outside of %{{...%}} section\n",
+                           pretty_input_file_name (), lineno);
+                  std::process::exit(1);
+                }
+
+                if (!state._verbatim_open) {
+                    eprintln!("{}:{}: %{{...%}} section already closed\n",
+                     pretty_input_file_name (), lineno);
+                    std::process::exit(1);
+                }
+                state._verbatim_open = false;
+
+                /* Give a warning if the rest of the line is nonempty.  */
+                if (line[2..].iter().any(|&c| !(c == b' ' || c == b'\t' || c == b'\n'))) {
+                    eprintln!("{}:{}: warning: junk after %}} is ignored\n",
+                   pretty_input_file_name (), lineno);
+                }
+            } else if (state._verbatim_open) {
+                eprintln!("{}:{}: warning: % directives are ignored inside the %{{...%}} section\n",
+                pretty_input_file_name (), lineno);
+                self._verbatim_declarations.extend_from_slice(line);
+            } else if (is_set_declaration (line, lineno, &mut set_name)) {
+                state._set = Some(self.find_set(&set_name, lineno));
+            } else if let Some(set) = state._set {
+                self.parse_set_directive(set, line, lineno);
+            } else {
+                self.parse_directive(line, lineno);
+            }
+        } else if (state._verbatim_open) {
+            self._verbatim_declarations.extend_from_slice(line);
+        } else if let Some(set) = state._set {
+            self._sets[set]._struct_decl_text.extend_from_slice(line);
+            self._sets[set]._struct_decl_linenos.push(lineno);
+        } else {
+            /* Append the line to struct_decl, and its lineno to
+               struct_decl_linenos.  */
+            state._struct_decl.extend_from_slice(line);
+            state._struct_decl_linenos.push(lineno);
+        }
+    }
+
+    /* Handles a % directive in the declarations section.  */
+    fn parse_directive(&mut self, line: &[u8], lineno: u32) {
+
+        let mut arg: Vec<u8> = Vec::new();
+
+        if (is_declaration_with_arg (line, lineno, "delimiters", &mut arg)) {
+            option.set_delimiters(option_arg(&arg));
+        } else if (is_declaration (line, lineno, "struct-type")) {
+            option.set(TYPE);
+        } else if (is_declaration (line, lineno, "ignore-case")) {
+            option.set(UPPERLOWER);
+        } else if (is_declaration_with_arg (line, lineno, "language", &mut arg)) {
+            option.set_language(option_arg(&arg));
+        } else if (is_declaration_with_arg (line, lineno, "lookup", &mut arg)) {
+            option.set_lookup(option_arg(&arg));
+        } else if (is_declaration_with_arg (line, lineno, "compare-function", &mut arg)) {
+            option.set_compare_function(option_arg(&arg));
+        } else if (is_char_equiv_declaration (line, lineno, &mut arg)) {
+            if (arg.len() < 2 || arg.contains(&0)) {
+                eprintln!("{}:{}: %char-equiv needs at least two characters, none of them NUL\n",
+                          pretty_input_file_name (), lineno);
+                std::process::exit(1);
+            }
+            option.add_char_equiv(arg);
+        } else if (is_define_declaration (line, lineno, "slot-name", &mut arg)) {
+            option.set_slot_name(option_arg(&arg));
+        } else if (is_define_declaration (line, lineno, "initializer-suffix", &mut arg)) {
+            option.set_initializer_suffix(option_arg(&arg));
+        } else if (is_define_declaration (line, lineno, "hash-function-name", &mut arg)) {
+            option.set_hash_name(option_arg(&arg));
+        } else if (is_define_declaration (line, lineno, "lookup-function-name", &mut arg)) {
+            option.set_function_name(option_arg(&arg));
+        } else if (is_declaration (line, lineno, "7bit")) {
+            option.set(SEVENBIT);
+        } else if (is_declaration (line, lineno, "compare-lengths")) {
+            option.set(LENTABLE);
+        } else if (is_declaration (line, lineno, "compare-strncmp")) {
+            option.set(COMP);
+        } else if (is_declaration (line, lineno, "readonly-tables")) {
+            option.set(CONST);
+        } else if (is_declaration (line, lineno, "enum")) {
+            option.set(ENUM);
+        } else if (is_declaration (line, lineno, "includes")) {
+            option.set(INCLUDE);
+        } else if (is_declaration (line, lineno, "global-table")) {
+            option.set(GLOBAL);
+        } else if (is_declaration (line, lineno, "pic")) {
+            option.set(SHAREDLIB);
+        } else if (is_define_declaration (line, lineno, "string-pool-name", &mut arg)) {
+            option.set_stringpool_name(option_arg(&arg));
+        } else if (is_declaration (line, lineno, "null-strings")) {
+            option.set(NULLSTRINGS);
+        } else if (is_define_declaration (line, lineno, "word-array-name", &mut arg)) {
+            option.set_wordlist_name(option_arg(&arg));
+        } else if (is_define_declaration (line, lineno, "length-table-name", &mut arg)) {
+            option.set_lengthtable_name(option_arg(&arg));
+        } else if (is_declaration_with_arg (line, lineno, "switch", &mut arg)) {
+            option.set_total_switches(String::from_utf8_lossy(&arg).parse::<i32>().unwrap_or(0));
+            if (option.get_total_switches() <= 0) {
+                eprintln!("{}:{}: number of switches {} must be a postive number\n",
+                          pretty_input_file_name(), lineno, String::from_utf8_lossy(&arg));
+                std::process::exit(1);
+            }
+        } else if (is_declaration (line, lineno, "omit-struct-type")) {
+            option.set(NOTYPE);
+        } else {
+            eprintln!("{}:{}: unrecognized % directive\n",
+                       pretty_input_file_name (), lineno);
+            std::process::exit(1);
+        }
+    }
+
+    /* Determines _struct_decl, _return_type, _struct_tag from the
+       non-directive lines of the declarations section.  */
+    fn set_struct_decl(&mut self, struct_decl: Vec<u8>, struct_decl_linenos: Vec<u32>) {
+        let (decl, lineno, tag, return_type) = Input::parse_struct_decl(struct_decl, struct_decl_linenos);
+        self._struct_decl = decl;
+        self._struct_decl_lineno = lineno;
+        self._struct_tag = tag;
+        self._return_type = return_type;
+    }
+
+    /* Returns the struct declaration, its line number, the naked "struct
+       something" and the return type of the lookup function.  */
+    fn parse_struct_decl(mut struct_decl: Vec<u8>, struct_decl_linenos: Vec<u32>) -> (String, u32, String, String) {
+
+        let mut struct_decl_lineno: u32 = 0;
+        if (!struct_decl.is_empty()) {
+            /* Drop leading whitespace and comments.  */
+            let mut p: usize = 0;
+            let mut l: usize = 0;
+            loop {
+                if (p < struct_decl.len() && (struct_decl[p] == b' ' || struct_decl[p] == b'\t')) {
+                    p += 1;
+                    continue;
+                }
+
+                if (p < struct_decl.len() && struct_decl[p] == b'\n') {
+                    l += 1;
+                    p += 1;
+                    continue;
+                }
+
+                if (p + 1 < struct_decl.len() && struct_decl[p] == b'/') {
+                    if (struct_decl[p + 1] == b'*') {
+                        /* Skip over ANSI C style comment.  */
+                        p += 2;
+                        while (p < struct_decl.len()) {
+                            if (struct_decl[p] == b'*' && p + 1 < struct_decl.len() && struct_decl[p + 1] == b'/') {
+                                p += 2;
+                                break;
+                            }
+                            if (struct_decl[p] == b'\n') {
+                                l += 1;
+                            }
+                            p += 1;
+                        }
+                        continue;
+                    }
+
+                    if (struct_decl[p + 1] == b'/') {
+                        /* Skip over ISO C99 or C++ style comment.  */
+                        p += 2;
+                        while (p < struct_decl.len() && struct_decl[p] != b'\n') {
+                            p += 1;
                         }
+                        if (p < struct_decl.len()) {
+                            l += 1;
+                            p += 1;
+                        }
+                        continue;
                     }
-                    lineno += 1;
+                }
+                break;
+            }
+
+            struct_decl.drain(0..p);
+            if (l < struct_decl_linenos.len()) {
+                struct_decl_lineno = struct_decl_linenos[l];
+            }
+
+            /* Drop trailing whitespace.  */
+            while (struct_decl.last().map_or(false, |&c| c == b'\n' || c == b' ' || c == b'\t')) {
+                struct_decl.pop();
+            }
+        }
+
+        if (struct_decl.is_empty()) {
+          eprintln!("{}: missing struct declaration for option --struct-type\n",
+                   pretty_input_file_name ());
+          std::process::exit(1);
+        }
+
+        /* Ensure trailing semicolon.  */
+        if (*struct_decl.last().unwrap() != b';') {
+            struct_decl.push(b';');
+        }
+
+        /* The entire declaration.  */
+        let decl: String = String::from_utf8_lossy(&struct_decl).into_owned();
+
+        /* The naked "struct something".  */
+        let mut p: usize = 0;
+        while (p < struct_decl.len() && struct_decl[p] != b'{' && struct_decl[p] != b';' && struct_decl[p] != b'\n') {
+            p += 1;
+        }
+        while (p > 0 && (struct_decl[p - 1] == b'\n' || struct_decl[p - 1] == b' ' || struct_decl[p - 1] == b'\t')) {
+            p -= 1;
+        }
+        let tag: String = String::from_utf8_lossy(&struct_decl[0..p]).into_owned();
+
+        /* The return type of the lookup function is "struct something *".
+           No "const" here, because if !option[CONST], some user code might
+           want to modify the structure. */
+        let return_type: String = format!("{} *", tag);
+        return (decl, struct_decl_lineno, tag, return_type);
+    }
+
+    /* Returns the index of the set named NAME, creating it if it was not
+       mentioned before.  */
+    fn find_set(&mut self, name: &[u8], lineno: u32) -> usize {
+        let name: String = String::from_utf8_lossy(name).into_owned();
+        if let Some(i) = self._sets.iter().position(|set| set._name == name) {
+            return i;
+        }
+
+        let mut chars = name.chars();
+        if (!chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')) {
+            eprintln!("{}:{}: set name {} is not an identifier\n",
+                      pretty_input_file_name(), lineno, name);
+            std::process::exit(1);
+        }
+
+        option.add_set_name(name.clone());
+        let mut set: Box<Keyword_Set> = Box::new(Keyword_Set {
+            _name: name,
+            _file_name: current_file_name,
+            _lineno: lineno,
+            _function_name: null(),
+            _wordlist_name: null(),
+            _hash_name: null(),
+            _type: false,
+            _struct_decl_text: Vec::new(),
+            _struct_decl_linenos: Vec::new(),
+            _struct_decl: String::new(),
+            _struct_decl_lineno: 0,
+            _struct_fields: Vec::new(),
+            _return_type: String::new(),
+            _struct_tag: String::new(),
+            _head: null_mut(),
+            _list_tail: null_mut()
+        });
+        set._list_tail = &mut set._head;
+        self._sets.push(set);
+        return self._sets.len() - 1;
+    }
+
+    /* Makes the following keywords go to SET, or outside of any set.  */
+    fn select_set(&mut self, set: Option<usize>) {
+        match self._current_set {
+            Some(i) => self._sets[i]._list_tail = self._list_tail,
+            None => self._default_tail = self._list_tail
+        }
+        self._list_tail = match set {
+            Some(i) => self._sets[i]._list_tail,
+            None => self._default_tail
+        };
+        self._current_set = set;
+    }
+
+    /* Handles a % directive after %set in the declarations section.  */
+    fn parse_set_directive(&mut self, set: usize, line: &[u8], lineno: u32) {
+
+        let mut arg: Vec<u8> = Vec::new();
+        let set: &mut Keyword_Set = &mut self._sets[set];
+
+        if (is_declaration (line, lineno, "struct-type")) {
+            set._type = true;
+        } else if (is_define_declaration (line, lineno, "lookup-function-name", &mut arg)) {
+            set._function_name = option_arg(&arg);
+        } else if (is_define_declaration (line, lineno, "word-array-name", &mut arg)) {
+            set._wordlist_name = option_arg(&arg);
+        } else if (is_define_declaration (line, lineno, "hash-function-name", &mut arg)) {
+            set._hash_name = option_arg(&arg);
+        } else {
+            eprintln!("{}:{}: only %struct-type and the lookup-function-name, word-array-name and hash-function-name %defines are allowed after %set\n",
+                      pretty_input_file_name (), lineno);
+            std::process::exit(1);
+        }
+    }
+
+    /* Determines the struct declaration of set I and checks the
+       initializers of its keywords.  */
+    fn finish_set(&mut self, i: usize) {
+        let set: &mut Keyword_Set = &mut self._sets[i];
+        if (!set._type) {
+            return;
+        }
+
+        current_file_name = set._file_name;
+        let (decl, lineno, tag, return_type) = Input::parse_struct_decl(std::mem::take(&mut set._struct_decl_text),
+                                                                        std::mem::take(&mut set._struct_decl_linenos));
+        set._struct_decl = decl;
+        set._struct_decl_lineno = lineno;
+        set._struct_tag = tag;
+        set._return_type = return_type;
+        set._struct_fields = parse_struct_fields(&set._struct_decl, set._file_name, set._struct_decl_lineno);
+
+        let mut temp: *mut Keyword_List = set._head;
+        while (temp != null_mut()) {
+            check_initializers(&set._struct_fields, &mut *(*temp).first());
+            temp = (*temp).rest();
+        }
+    }
 
-                    
+    /* Returns the keyword lists of all sets, the keywords outside of any
+       set first.  */
+    pub fn all_keyword_lists(&self) -> Vec<*mut Keyword_List> {
+        let mut result: Vec<*mut Keyword_List> = vec![self._head];
+        for set in self._sets.iter() {
+            result.push(set._head);
+        }
+        return result;
+    }
+
+    /* Parses a keyword line.  Returns the new keyword, or null for a
+       comment line.  */
+    fn parse_keyword_line(&mut self, line: &[u8], lineno: u32, delimiters: &[u8],
+                          charset_dependent: &mut bool) -> *mut Keyword {
+
+        if (line[0] == b'#') {
+            /* Comment Line. */
+            return null_mut();
+        }
+
+        if (line[0] == b'%') {
+            eprintln!("{}:{}: declarations are not allowed in the keywords section.\n To declare a keyword starting with %, enclose it in double-quotes.\n",
+                 pretty_input_file_name (), lineno);
+            std::process::exit(1);
+        }
+
+        /* The line without its newline.  */
+        let line_end: usize = if line.last() == Some(&b'\n') { line.len() - 1 } else { line.len() };
+
+        let keyword: Vec<u8>;
+        let mut lp: usize;
+
+        if (line[0] == b'"') {
+            /* Parse a string in ANSI C syntax.  */
+            let (kw, end, kw_charset_dependent) = parse_string_keyword(line, lineno);
+            if (kw_charset_dependent) {
+                *charset_dependent = true;
+            }
+            keyword = kw;
+            lp = end;
+
+            if (lp < line_end) {
+                if (!delimiters.contains(&line[lp])) {
+                    eprintln!("{}:{}: string not followed by delimiter\n",
+                    pretty_input_file_name (), lineno);
+                    std::process::exit(1);
                 }
+                lp += 1;
+            }
+        } else {
+            /* Not a string.  Look for the delimiter.  */
+            lp = 0;
+            while (lp < line_end && !delimiters.contains(&line[lp])) {
+                lp += 1;
+            }
+            keyword = line[0..lp].to_vec();
+            if (lp < line_end) {
+                lp += 1;
+            }
+
+            if (keyword.len() > 0) {
+                *charset_dependent = true;
+            }
+        }
+
+        let with_type: bool = match self._current_set {
+            Some(set) => self._sets[set]._type,
+            None => option[TYPE]
+        };
+        let rest: Vec<u8> = if (with_type && lp < line_end) {
+            line[lp..line_end].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        /* Allocate Keyword.  */
+        let new_kw: *mut Keyword = (*self._factory).create_keyword (keyword, rest);
+        (*new_kw)._file_name = current_file_name;
+        (*new_kw)._lineno = lineno;
+        return new_kw;
+    }
+}
+
+
+/* Reads the next line, including its newline, into LINE.
+   Returns false at the end of the input.  */
+fn read_line(stream: &mut BufReader<File>, line: &mut Vec<u8>) -> bool {
+    line.clear();
+    match stream.read_until(b'\n', line) {
+        Ok(n) => n > 0,
+        Err(_) => {
+            eprintln!("{}: error while reading input file\n", pretty_input_file_name());
+            process::exit(1);
+        }
+    }
+}
+
+/* An input file is read twice.  If it is not seekable (a pipe), copy it
+   into an unlinked temporary file first.  */
+fn make_seekable(mut stream: BufReader<File>) -> BufReader<File> {
+    if stream.seek(SeekFrom::Current(0)).is_ok() {
+        return stream;
+    }
+
+    let path = std::env::temp_dir().join(format!("gperf-input-{}", process::id()));
+    let mut spool: File = match std::fs::OpenOptions::new().read(true).write(true)
+                                    .create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(_) => {
+            eprintln!("{}: cannot create temporary file {}\n",
+                      pretty_input_file_name(), path.display());
+            process::exit(1);
+        }
+    };
+    std::fs::remove_file(&path).ok();
+
+    if std::io::copy(&mut stream, &mut spool).is_err()
+        || spool.seek(SeekFrom::Start(0)).is_err() {
+        eprintln!("{}: error while reading input file\n", pretty_input_file_name());
+        process::exit(1);
+    }
+    return BufReader::new(spool);
+}
+
+/* First pass: locates the %% separators.  Only one line is held in
+   memory at a time.  */
+fn scan_layout(stream: &mut BufReader<File>) -> Input_Layout {
+    let mut layout = Input_Layout {
+        _separator_lineno: [0, 0],
+        _separators: 0,
+        _percent_before_separator: false,
+        _lines: 0
+    };
+
+    let mut line: Vec<u8> = Vec::new();
+    while read_line(stream, &mut line) {
+        layout._lines += 1;
+        if line.starts_with(b"%%") {
+            if layout._separators < 2 {
+                layout._separator_lineno[layout._separators] = layout._lines;
+                layout._separators += 1;
+            }
+        } else if line[0] == b'%' && layout._separators == 0 {
+            layout._percent_before_separator = true;
+        }
+    }
+
+    if stream.seek(SeekFrom::Start(0)).is_err() {
+        eprintln!("{}: error while reading input file\n", pretty_input_file_name());
+        process::exit(1);
+    }
+    return layout;
+}
+
+
+/* Returns the 0-based column holding the keyword in CSV, TSV and JSON
+   array records.  */
+fn key_field_column() -> usize {
+
+    if (option.get_key_field() == null()) {
+        return 0;
+    }
+
+    match String::from_utf8_lossy(&option_string(option.get_key_field())).parse::<usize>() {
+        Ok(column) if column >= 1 => column - 1,
+        _ => {
+            eprintln!("{}: --key-field must be a column number starting at 1\n",
+                      pretty_input_file_name());
+            std::process::exit(1);
+        }
+    }
+}
+
+
+/* Reads one record of a CSV or TSV file into FIELDS.  With QUOTING, a
+   field may be enclosed in double-quotes as in RFC 4180; it may then
+   contain the separator, line breaks, and "" for a double-quote.  LINENO
+   is advanced past the record.  Returns false at the end of the input.  */
+fn read_record(stream: &mut BufReader<File>, separator: u8, quoting: bool,
+               lineno: &mut u32, fields: &mut Vec<Vec<u8>>) -> bool {
+
+    let mut line: Vec<u8> = Vec::new();
+    fields.clear();
+    if (!read_line(stream, &mut line)) {
+        return false;
+    }
+    *lineno += 1;
+    let record_lineno: u32 = *lineno;
+
+    let mut field: Vec<u8> = Vec::new();
+    let mut in_quotes: bool = false;
+    let mut quoted: bool = false;
+    let mut i: usize = 0;
+
+    loop {
+        if (i >= line.len()) {
+            if (!in_quotes) {
+                break;
+            }
+            /* A quoted field continues on the next line.  */
+            if (!read_line(stream, &mut line)) {
+                eprintln!("{}:{}: unterminated quoted field\n",
+                          pretty_input_file_name(), record_lineno);
+                std::process::exit(1);
             }
+            *lineno += 1;
+            i = 0;
+            continue;
+        }
 
-            let has_declarations: bool;
-            if separators == 1 {
-                if options[TYPE] {
-                    has_declarations = true;
+        let c: u8 = line[i];
+        if (in_quotes) {
+            if (c == b'"') {
+                if (i + 1 < line.len() && line[i + 1] == b'"') {
+                    field.push(b'"');
+                    i += 2;
                 } else {
-                    has_declarations = false;
-                    let mut p: *const char = input;
-                    while p < separator[0] {
-                        if *p == '%' {
-                            has_declarations = true;
-                            break;
-                        }
-
-                        let tmp = p;
-                        for i in 0..((separator[0] as usize - tmp as usize) / 4) {
-                        
-                            if(p == null_mut()) {
-                                p = separator[0];
-                                break;
-                            } else if *p == '\n' {
-                                p = p.offset(1);
-                                break;
-                            }
-                            p = p.offset(1);
-                        } 
-                    }
+                    in_quotes = false;
+                    i += 1;
                 }
-
             } else {
-                has_declarations = (separators > 0);
+                field.push(c);
+                i += 1;
             }
+        } else if (c == separator) {
+            fields.push(std::mem::take(&mut field));
+            quoted = false;
+            i += 1;
+        } else if (c == b'\n' || (c == b'\r' && i + 1 < line.len() && line[i + 1] == b'\n')) {
+            break;
+        } else if (quoting && c == b'"' && field.is_empty() && !quoted) {
+            in_quotes = true;
+            quoted = true;
+            i += 1;
+        } else if (quoted) {
+            eprintln!("{}:{}: junk after quoted field\n",
+                      pretty_input_file_name(), *lineno);
+            std::process::exit(1);
+        } else {
+            field.push(c);
+            i += 1;
+        }
+    }
 
+    fields.push(field);
+    return true;
+}
 
-            if (has_declarations) {
-                declarations = input;
-                declarations = separator[0];
 
-                /* Give a warning if the separator line is nonempty.  */
-                let nonempty_line: bool = false;
-                let mut p: *const char;
+/* A minimal JSON reader, reading one byte at a time.  Only what the tool
+   reads is supported: keyword files, which are arrays of flat objects or
+   arrays, and the --previous and model files it writes itself.  */
+struct Json_Reader {
+    _stream: BufReader<File>,
+    _peeked: Option<u8>,
+    _lineno: u32,
 
-                p = declarations_end.add(2);
+    /* The file name, for error messages.  */
+    _file_name: String
+}
 
-                while p < input_end {
-                    if (*p == '\n') {
-                        p = p.offset(1);
-                        break;
-                    }
-                    
-                    if (!(*p == ' ' || *p == '\t')) {
-                        nonempty_line = true;
-                    }
+impl Json_Reader {
 
-                    p = p.offset(1);
-                        
-                }
-                
-                if(nonempty_line) {
-                    eprintln!("{}:{}: warning: junk after %% is ignored\n",
-                            pretty_input_file_name(), separator_lineno[0]);   
-                }
+    pub fn new(stream: File, file_name: String) -> Json_Reader {
+        Json_Reader {
+            _stream: BufReader::new(stream),
+            _peeked: None,
+            _lineno: 1,
+            _file_name: file_name
+        }
+    }
 
-                keywords = p;
-                keywords_lineno = separator_lineno[0] + 1;
-            } else {
-                declarations = null_mut();
-                declarations_end = null_mut();
-                keywords = input;
-                keywords_lineno = 1;
-            }
+    pub fn error(&self, message: &str) -> ! {
+        eprintln!("{}:{}: {}\n", self._file_name, self._lineno, message);
+        std::process::exit(1);
+    }
 
-            if (separators > (if has_declarations {true} else {false})) {
-                keywords_end = separator[separators-1];
-                _verbatim_code = separator[separators-1].add(2);
-                _verbatim_code_end = input_end;
-                _verbatim_code_lineno = separator_lineno[separators-1];
-            } else {
-                keywords_end = input_end;
-                _verbatim_code = null_mut();
-                _verbatim_code_end = null_mut();
-                _verbatim_code_lineno = 0;
-            }
-
-        }
-
-        _verbatim_declarations = null_mut();
-        _verbatim_declarations_end = null_mut();
-        _verbatim_declarations_lineno = 0;
-        _struct_decl = null_mut();
-        _struct_decl_lineno = 0;
-        _return_type = null_mut();
-        _struct_tag = null_mut();
-        
-        {
-            let mut lineno: u32 = 1;
-            let mut struct_decl: *mut char = null_mut();
-            let mut struct_decl_linenos: *mut u32 = null_mut();
-            let mut struct_decl_linecount: u32 = 0;
-            
-            let mut line: *const char = declarations;
-            while (line < declarations_end) {
-                let mut line_end: *const char = line;
-                
-                let tmp = line;
-                for i in 0..((input_end as usize - tmp as usize) / 4) {
-                        
-                    if(line_end == null_mut()) {
-                        line_end = declarations_end;
-                        break;
-                    } else if *line_end == '\n' {
-                        line_end = line_end.offset(1);
-                        break;
-                    }
-                    line_end = line_end.offset(1);
-                } 
-                
-                if (*line == '%') {
-                    if line[1] == '{' {
-                        /* Handle %{.  */
-                        if (_verbatim_declarations != null_mut()) {
-                            eprintln!("{}:{}:\n{}:{}: only one %{...%} section is allowed\n",
-                            pretty_input_file_name (),
-                            _verbatim_declarations_lineno,
-                            pretty_input_file_name (), lineno);
-                            std::process::exit(1);
-                        }
-                        _verbatim_declarations = line.add(2);
-                        _verbatim_declarations_lineno = lineno; 
-                    } else if (*line.offset(1) == '}') {
-                        if (_verbatim_declarations == null_mut())
-                        {
-                          eprintln!("{}:{}:"
-                                   " %} outside of %{...%} section\n",
-                                   pretty_input_file_name (), lineno);
-                          std::process::exit(1);
-                        }
-                        
-                        if (_verbatim_declarations_end != null_mut()) {
-                            eprintln!(stderr, "{}:{}: %{...%} section already closed\n",
-                             pretty_input_file_name (), lineno);
-                            std::process::exit(1);
-                        }
-                        _verbatim_declarations_end = line;
-                        /* Give a warning if the rest of the line is nonempty.  */
-                        let mut nonempty_line = false;
-                        let mut q: *const char;
-                        
-                        q = line.offset(2);
-                        while (q < line_end) {
-                            
-                            if(*q == '\n') {
-                                q = q.offset(1);
-                                break;
-                            } 
+    fn peek(&mut self) -> Option<u8> {
+        if (self._peeked == None) {
+            let mut byte: [u8; 1] = [0];
+            self._peeked = match self._stream.read(&mut byte) {
+                Ok(1) => Some(byte[0]),
+                Ok(_) => None,
+                Err(_) => self.error("error while reading input file")
+            };
+        }
+        return self._peeked;
+    }
 
-                            if (!(*q == ' ' || *q == '\t')) {
-                                nonempty_line = true;
-                            }
-                                                    
-                            q = q.offset(1);
-                        }
+    fn next(&mut self) -> Option<u8> {
+        let c: Option<u8> = self.peek();
+        self._peeked = None;
+        if (c == Some(b'\n')) {
+            self._lineno += 1;
+        }
+        return c;
+    }
 
-                        if (nonempty_line) {
-                            eprintln!("{}:{}: warning: junk after %} is ignored\n",
-                           pretty_input_file_name (), lineno);
-                        }
-                    } else if (_verbatim_declarations != null_mut() 
-                                && _verbatim_declarations_end == null_mut()) {
-                                    eprintln!("{}:{}: warning: % directives are ignored inside the %{...%} section\n",
-                                    pretty_input_file_name (), lineno); 
-                      } else {
-
-                            let mut arg: *mut char;
-                            
-                            if (is_declaration_with_arg (line, line_end, lineno,
-                                "delimiters", &arg)) {
-                                option.set_delimiters(arg);
-                            } else if (is_declaration (line, line_end, lineno, "struct-type")) {
-                                option.set(TYPE);
-                            } else if (is_declaration (line, line_end, lineno, "ignore-case")) {
-                                option.set(UPPERLOWER);
-                            } else if (is_declaration_with_arg (line, line_end, lineno,
-                                "language", &arg)) {
-                                option.set_language(arg);  
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                "slot-name", &arg)) {
-                                option.set_slot_name(arg); 
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                "initializer-suffix", &arg)) {
-                                option.set_initializer_suffix(arg);
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                "hash-function-name", &arg)) {
-                                option.set_hash_name(arg);
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                "lookup-function-name", &arg)) {
-                                option.set_function_name(arg);
-                            } else if (is_declaration (line, line_end, lineno, "7bit")) {
-                                option.set(SEVENBIT);
-                            } else if (is_declaration (line, line_end, lineno, "compare-lengths")) {
-                                option.set(LENTABLE);
-                            }
-                            else if (is_declaration (line, line_end, lineno, "compare-strncmp")) {
-                                option.set(COMP);
-                            } else if (is_declaration (line, line_end, lineno, "readonly-tables")) {
-                                option.set(CONST);
-                            } else if (is_declaration (line, line_end, lineno, "enum")) {
-                                option.set(ENUM);
-                            } else if (is_declaration (line, line_end, lineno, "includes")) {
-                                option.set(INCLUDE);
-                            } else if (is_declaration (line, line_end, lineno, "global-table")) {
-                              option.set(GLOBAL);
-                            } else if (is_declaration (line, line_end, lineno, "pic")) {
-                              option.set(SHAREDLIB);
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                                       "string-pool-name", &arg)) {
-                              option.set_stringpool_nam(arg);
-                            } else if (is_declaration (line, line_end, lineno, "null-strings")) {
-                                option.set(NULLSTRINGS);
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                           "word-array-name", &arg)) {
-                                option.set_wordlist_name(arg);
-                            } else if (is_define_declaration (line, line_end, lineno,
-                                                       "length-table-name", &arg)) {
-                                option.set_lengthtable_name(arg);
-                            } else if (is_declaration_with_arg (line, line_end, lineno,
-                                "switch", &arg)) {
-                                option.set_total_switches(Cstr::from_ptr(arg).to_str().parse::<i32>().unwrap());
-                                if (option.get_total_switches() <= 0) {
-                                    eprintln!("{}:{}: number of switches {} must be a postive number\n", pretty_input_file_name(), lineno, Cstr::from_str(arg));
-                                    std::process::exit(1);
-                                } 
-                                
-                            } else if (is_declaration (line, line_end, lineno, "omit-struct-type")) {
-                                    option.set(NOTYPE);
-                            } else {
-                                eprintln!("{}:{}: unrecognized % directive\n",
-                                           pretty_input_file_name (), lineno);
-                                std::process::exit(1);
-                            }
-                            
-                        } 
-                    
-                    } else if (!(_verbatim_declarations != null_mut()
-                        && _verbatim_declarations_end == null_mut())) {
-                            /* Append the line to struct_decl.  */
-
-                            let mut old_len: usize = if(struct_decl == null_mut()) {libc::strlen(struct_decl)} else {0};
-                            let mut line_len: usize = (line_end as usize - line as usize) / 4;
-                            let mut new_len: usize = old_len + line_len + 1;
-
-                            let mut new_struct_decl: [*mut char; new_len];
-                            if (old_len > 0) {
-                                copy_nonoverlapping(struct_decl, new_struct_decl, old_len);
-                            }
-                            let mut temp = new_struct_decl.offset(old_len);
-                            copy_nonoverlapping(line, temp, line_len);
-                            *new_struct_decl.offset(old_len + line_len) = '\0';
-                            if(struct_decl != null_mut()) {
-                                std::mem::drop(struct_decl);
-                            }
-                            struct_decl = new_struct_decl;
-                            /* Append the lineno to struct_decl_linenos.  */
-                            let new_struct_decl_linenos: *mut u32 = &mut [0;struct_decl_linecount + 1] as *mut u32;
-                            if(struct_decl_linecount > 0) {
-                                copy_nonoverlapping(struct_decl_linenos, new_struct_decl_linenos
-                                    , struct_decl_linecount * std::mem::size_of_<u32>());
-                            }
-                            *new_struct_decl_linenos.offset(struct_decl_linecount) = lineno;
-                            if (struct_decl_linenos != null_mut()) {
-                                std::mem::drop(struct_decl_linenos);
-                            }
-                            struct_decl_linenos = new_struct_decl_linenos;
-                            /* Increment struct_decl_linecount.  */
-                            struct_decl_linecount += 1;
-                        }
-                        lineno += 1;
-                        line = line_end;
-            }
+    /* Consumes C, after whitespace, if it is next.  */
+    pub fn accept(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if (self.peek() == Some(c)) {
+            self.next();
+            return true;
+        }
+        return false;
+    }
 
-            if (_verbatim_declarations != null_mut() && _verbatim_declarations_end == null_mut()) {
-                    eprintln! ("{}:{}: unterminated %{ section\n",
-                        pretty_input_file_name (), _verbatim_declarations_lineno);
-                    std::process::exit(1);
-            }
+    /* Consumes C, after whitespace.  */
+    pub fn expect(&mut self, c: u8) {
+        if (!self.accept(c)) {
+            self.error(&format!("expected '{}'", c as char));
+        }
+    }
 
-    /* Determine _struct_decl, _return_type, _struct_tag.  */
-    if (option[TYPE])
-      {
-          if (struct_decl != null_mut())
-          {
-            /* Drop leading whitespace and comments.  */
-            {
-              let mut p: *mut char = struct_decl;
-              let mut l: *mut u32 = struct_decl_linenos;
-              loop
-              {
-                if (*p == ' ' || *p == '\t'){
-                      p = p.offset(1);
-                      continue;
-                    }
+    pub fn skip_whitespace(&mut self) {
+        while (matches!(self.peek(), Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r'))) {
+            self.next();
+        }
+    }
 
-                  if (*p == '\n'){
-                      l = l.offset(1);
-                      p = p.offset(1);
-                      continue;
-                    }
+    /* Reads four hex digits of a \u escape.  */
+    fn parse_hex4(&mut self) -> u32 {
+        let mut code: u32 = 0;
+        for _ in 0..4 {
+            match self.next().and_then(|c| (c as char).to_digit(16)) {
+                Some(digit) => code = (code << 4) + digit,
+                None => self.error("invalid \\u escape in string")
+            }
+        }
+        return code;
+    }
 
-                    if (*p == '/') {
-                      
-                        if (*p.offset(1) == '*') {
-                          /* Skip over ANSI C style comment.  */
-                          p = p.offset(2);
-                          while (*p != '\0') {
-                              if (*p == '*' && *p.offset(1) == '/') {
-                                  p = p.offset(2);
-                                  break;
+    /* Reads a string and returns its contents, encoded as UTF-8.  */
+    pub fn parse_string(&mut self) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::new();
+        self.expect(b'"');
+        loop {
+            match self.next() {
+                None | Some(b'\n') => self.error("unterminated string"),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let c: u8 = match self.next() {
+                        Some(b'"') => b'"',
+                        Some(b'\\') => b'\\',
+                        Some(b'/') => b'/',
+                        Some(b'b') => 0x08,
+                        Some(b'f') => 0x0C,
+                        Some(b'n') => b'\n',
+                        Some(b'r') => b'\r',
+                        Some(b't') => b'\t',
+                        Some(b'u') => {
+                            let mut code: u32 = self.parse_hex4();
+                            if (code >= 0xD800 && code < 0xDC00) {
+                                /* A surrogate pair.  */
+                                if (self.next() != Some(b'\\') || self.next() != Some(b'u')) {
+                                    self.error("invalid surrogate pair in string");
                                 }
-                              if (p[0] == '\n') {
-                                l = l.offset(1);
-                              }
-                                l = l.offset(1);
-                                p = p.offset(1);
+                                let low: u32 = self.parse_hex4();
+                                if (!(low >= 0xDC00 && low < 0xE000)) {
+                                    self.error("invalid surrogate pair in string");
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
                             }
-                          continue;
-                        }
-
-                      if (*p.offset(1) == '/') {
-                          /* Skip over ISO C99 or C++ style comment.  */
-                          p = p.offset(2);
-                          while (*p != '\0' && *p != '\n') {
-                            p = p.offset(1);
-                          }
-                            
-                          if (*p == '\n') {
-                              l = l.offset(1);
-                              p = p.offset(1);
+                            match char::from_u32(code) {
+                                Some(ch) => {
+                                    let mut buf: [u8; 4] = [0; 4];
+                                    result.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                                }
+                                None => self.error("invalid \\u escape in string")
                             }
-
-                          continue;
+                            continue;
                         }
-                    }
-                  break;
-              }
-
-              if (p != struct_decl) {
-                let mut len: usize = libc::strlen(p);
-                let mut new_struct_decl: *mut char = &mut ['\0';len + 1] as *mut char;
-                copy_nonoverlapping(p, new_struct_decl, len + 1);
-                std::mem::drop(struct_decl);
-                struct_decl = new_struct_decl;
-              }
-              _struct_decl_lineno = *l;
-
-        }
-        /* Drop trailing whitespace.  */
-        let mut p: *mut char = struct_decl.offset(libc::strlen(struct_decl));
-        while (p > struct_decl) {
-            if (*p.offset(-1) == '\n' || *p.offset(-1) == ' ' || *p.offset(-1) == '\t') {
-                p = p.offset(-1);
-                *p = '\0';
-            } else {
-                break;
+                        _ => self.error("invalid escape sequence in string")
+                    };
+                    result.push(c);
+                }
+                Some(c) => result.push(c)
             }
         }
+        return result;
     }
-    
-    if (struct_decl == null_mut() || *struct_decl == '\0') {
-      eprintln!("{}: missing struct declaration"
-               " for option --struct-type\n",
-               pretty_input_file_name ());
-      std::process::exit(1);
-    }
-    {
-        /* Ensure trailing semicolon.  */
-        let mut old_len: usize = libc::strlen(struct_decl);
-        if (*struct_decl.offset(old_len - 1) != ';') {
-            let mut new_struct_decl: *mut char = &mut ['\0'; old_len + 2] as *mut char;
-            copy_nonoverlapping(struct_decl, new_struct_decl, old_len);
-            *new_struct_decl.offset(old_len) = ';';
-            *new_struct_decl.offset(old_len + 1) = '\0';
-            std::mem::drop(struct_decl);
-            struct_decl = new_struct_decl;
-        }
-    }
-    /* Set _struct_decl to the entire declaration.  */
-    _struct_decl = struct_decl;
-    /* Set _struct_tag to the naked "struct something".  */    
-    let mut p: *const char = struct_decl;
-    while (*p != '\0' && *p != '{' && *p != ';' && *p != '\n') {
-        p = p.offset(1);
-    }
-    while (p < struct_decl) {
-        if (*p.offset(-1) == '\n' || *p.offset(-1) == ' ' || *p.offset(-1) == '\t') {
-            p = p.offset(-1);
-        } else {
-            break;
+
+    /* Reads an integer.  */
+    pub fn parse_integer(&mut self) -> i64 {
+        self.skip_whitespace();
+        let mut text: Vec<u8> = Vec::new();
+        if (self.peek() == Some(b'-')) {
+            text.push(b'-');
+            self.next();
+        }
+        while (matches!(self.peek(), Some(c) if c.is_ascii_digit())) {
+            text.push(self.next().unwrap());
+        }
+        match std::str::from_utf8(&text).ok().and_then(|s| s.parse::<i64>().ok()) {
+            Some(value) => value,
+            None => self.error("expected an integer")
         }
     }
 
-    let mut struct_tag_length: usize = (p as usize - struct_decl as usize) / 4;
-    let mut struct_tag: *mut char = &mut ['\0'; struct_tag_length + 1] as *mut char;
-    copy_nonoverlapping(struct_decl, struct_tag, struct_tag_length);
-    *struct_tag.offset(struct_tag_length) = '\0';
-    _struct_tag = struct_tag;
+    /* Reads an array of integers.  */
+    pub fn parse_integer_array(&mut self) -> Vec<i32> {
+        let mut result: Vec<i32> = Vec::new();
+        self.expect(b'[');
+        if (!self.accept(b']')) {
+            loop {
+                result.push(self.parse_integer() as i32);
+                if (self.accept(b']')) {
+                    break;
+                }
+                self.expect(b',');
+            }
+        }
+        return result;
+    }
 
-    /* The return type of the lookup function is "struct something *".
-       No "const" here, because if !option[CONST], some user code might
-       want to modify the structure. */
+    /* Reads bytes written by json_bytes: a string, or an array of byte
+       values.  */
+    pub fn parse_bytes(&mut self) -> Vec<u8> {
+        self.skip_whitespace();
+        if (self.peek() != Some(b'[')) {
+            return self.parse_string();
+        }
+        let mut result: Vec<u8> = Vec::new();
+        for value in self.parse_integer_array() {
+            if (value < 0 || value > 255) {
+                self.error("byte value out of range");
+            }
+            result.push(value as u8);
+        }
+        return result;
+    }
 
-    let mut return_type: *mut char = &mut ['\0'; struct_tag_length + 3] as *mut char;
-    copy_nonoverlapping(struct_decl, return_type, struct_tag_length);
-    *return_type.offset(struct_tag_length) = ' ';
-    *return_type.offset(struct_tag_length + 1) = '*';
-    *return_type.offset(struct_tag_length + 2) = '\0';    
-    _return_type = return_type;
+    /* Reads a number, true, false or null and returns it as C text.  */
+    fn parse_scalar(&mut self) -> Vec<u8> {
+        let mut text: Vec<u8> = Vec::new();
+        while (matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'-' || c == b'+' || c == b'.')) {
+            text.push(self.next().unwrap());
+        }
+        match &text[..] {
+            b"true" => b"1".to_vec(),
+            b"false" | b"null" => b"0".to_vec(),
+            _ if !text.is_empty() && (text[0] == b'-' || text[0].is_ascii_digit()) => text,
+            _ => self.error("invalid value")
+        }
     }
-    
-    if (struct_decl_linenos != null_mut()) {
-        std::mem::drop(struct_decl_linenos);
+
+    /* Reads a field value.  Strings are taken without their quotes, so
+       that they can hold C expressions such as enumerators for fields that
+       are not strings.  */
+    fn parse_value(&mut self, name: Option<Vec<u8>>) -> Record_Field {
+        match self.peek() {
+            Some(b'"') => Record_Field { _name: name, _value: self.parse_string(), _text: true },
+            Some(b'{') | Some(b'[') => self.error("nested objects and arrays are not supported"),
+            _ => Record_Field { _name: name, _value: self.parse_scalar(), _text: false }
         }
     }
 
-    /* Parse the keywords section.  */
-    {
-        let mut list_tail: *mut *mut Keyword_List = &_head as *mut *mut Keyword_List;
-        let mut delimiters: *const char = option.get_delimiters();
-        let mut lineno: u32 = keywords_lineno;
-        let mut charset_dependent: bool = false;
+    /* Reads an object.  The member named by --key-field, or by the slot
+       name, is the keyword; the other members are the fields.  */
+    fn parse_object_record(&mut self) -> (Vec<u8>, Vec<Record_Field>) {
+        let key_name: Vec<u8> = option_string(if option.get_key_field() != null() {
+            option.get_key_field()
+        } else {
+            option.get_slot_name()
+        });
+        let mut keyword: Option<Vec<u8>> = None;
+        let mut fields: Vec<Record_Field> = Vec::new();
+
+        self.expect(b'{');
+        self.skip_whitespace();
+        if (self.peek() == Some(b'}')) {
+            self.next();
+        } else {
+            loop {
+                self.skip_whitespace();
+                let name: Vec<u8> = self.parse_string();
+                self.skip_whitespace();
+                self.expect(b':');
+                self.skip_whitespace();
+                if (name == key_name) {
+                    if (self.peek() != Some(b'"')) {
+                        self.error("keyword must be a string");
+                    }
+                    keyword = Some(self.parse_string());
+                } else {
+                    if (fields.iter().any(|f| f._name.as_ref() == Some(&name))) {
+                        self.error(&format!("duplicate member \"{}\"", String::from_utf8_lossy(&name)));
+                    }
+                    let field: Record_Field = self.parse_value(Some(name));
+                    fields.push(field);
+                }
+                self.skip_whitespace();
+                match self.next() {
+                    Some(b',') => continue,
+                    Some(b'}') => break,
+                    _ => self.error("expected ',' or '}'")
+                }
+            }
+        }
 
-        let mut line: *const char = keywords;
-        while (line < keywords_end) {
-            let mut line_end: *const char;
-        
-            for i in 0..((keywords_end as usize - line as usize) / 4) {
-                        
-                if(line_end == null_mut()) {
-                    line_end = keywords_end;
-                    break;
-                } else if *p == '\n' {
-                    line_end = line_end.offset(1);
-                    break;
+        match keyword {
+            Some(keyword) => (keyword, fields),
+            None => self.error(&format!("object has no member \"{}\"",
+                                        String::from_utf8_lossy(&key_name)))
+        }
+    }
+
+    /* Reads an array.  The element at the --key-field column is the
+       keyword; the other elements are the fields.  */
+    fn parse_array_record(&mut self) -> (Vec<u8>, Vec<Record_Field>) {
+        let key_column: usize = key_field_column();
+        let mut keyword: Option<Vec<u8>> = None;
+        let mut fields: Vec<Record_Field> = Vec::new();
+
+        self.expect(b'[');
+        self.skip_whitespace();
+        if (self.peek() == Some(b']')) {
+            self.next();
+        } else {
+            let mut column: usize = 0;
+            loop {
+                self.skip_whitespace();
+                if (column == key_column) {
+                    if (self.peek() != Some(b'"')) {
+                        self.error("keyword must be a string");
+                    }
+                    keyword = Some(self.parse_string());
+                } else {
+                    let field: Record_Field = self.parse_value(None);
+                    fields.push(field);
+                }
+                column += 1;
+                self.skip_whitespace();
+                match self.next() {
+                    Some(b',') => continue,
+                    Some(b']') => break,
+                    _ => self.error("expected ',' or ']'")
                 }
-                line_end = line_end.offset(1);
             }
+        }
 
-            if (*line == '#') {
-                /* Comment Line. */
-            }  else if (*line == '%') {
-                eprintln!("{}:{}: declarations are not allowed in the keywords section.\n To declare a keyword starting with %, enclose it in double-quotes.\n",
-                     pretty_input_file_name (), lineno);
-                std::process::exit(1);
-            } else {
-                let mut keyword: *const char;
-                let mut keyword_length: usize;
-                let mut rest: *const char;
-
-                if (*line == '"') {
-                    /* Parse a string in ANSI C syntax.  */
-                    let mut kp: *mut char = &mut ['\0'; (line_end as usize - line as usize)/4] as *mut char;
-                    keyword = kp;
-                    let mut lp: *const char = line.offset(1);
-
-                    loop {
-                        if (lp == line_end) {
-                            eprintln!("{}:{}: unterminated string\n",
-                                 pretty_input_file_name (), lineno);
-                            std::process::exit(1);
-                        }
+        match keyword {
+            Some(keyword) => (keyword, fields),
+            None => self.error(&format!("array has no element {}", key_column + 1))
+        }
+    }
+}
 
-                        let mut c: *mut char = lp;
-                        if (c == '\\') {
-                            lp = lp.offset(1);
-                            c = *lp;
-
-                            match c {
-                                '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7' => {
-                                    let mut code: i32 = 0;
-                                    let mut count: i32 = 0;
-
-                                    while (count < 3 && *lp >= '0' && *lp <= '7') {
-                                        code = (code << 3) + (*lp - '0');
-                                        lp = lp.offset(1);
-                                        count += 1;
-                                    }
-
-                                    if (code > 255 /*UCHAR_MAX*/) {
-                                        eprintln!("{}:{}: octal escape out of range\n", 
-                                        pretty_input_file_name (), lineno);
-                                        *kp = code as char;
-                                        
-                                    } 
-                                }
 
-                                'x' => {
-                                    let mut code: i32 = 0;
-                                    let mut count: i32 = 0;
-                                    lp = lp.offset(1); 
-                                    while ((*lp >= '0' && *lp <= '9')
-                                     || (*lp >= 'A' && *lp <= 'F')
-                                     || (*lp >= 'a' && *lp <= 'f')) {
-                                        code = (code << 4) + (if(*lp >= 'A' && *lp <= 'F') {
-                                            *lp - 'A' + 10
-                                        } else if (*lp >= 'a' && *lp <= 'f'){
-                                            *lp - 'a' + 10
-                                        } else {
-                                            *lp - '0'
-                                        });
-                                        lp = lp.offset(1);
-                                        count += 1;
-                                     }
-
-                                     if(count == 0) {
-                                        eprintln!("{}:{}: hexadecimal escape without any hex digits\n",
-                                                    pretty_input_file_name(), lineno);
-                                     }
-
-                                     if(code > 255) {
-                                        eprintln!("{}:{}: hexadecimal escape out of range\n",
-                                        pretty_input_file_name (), lineno);
-                                        *kp = code as char;
-                                        
-                                     }
-                                }
+/* Writes BYTES as a C string literal.  Bytes from 0x80 on are kept, so
+   that UTF-8 text stays readable.  */
+fn c_string_literal(bytes: &[u8]) -> Vec<u8> {
+    let mut result: Vec<u8> = vec![b'"'];
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' | b'?' => result.extend([b'\\', b]),
+            0x20..=0x7e | 0x80..=0xff => result.push(b),
+            _ => result.extend(format!("\\{:03o}", b).bytes())
+        }
+    }
+    result.push(b'"');
+    return result;
+}
 
-                                '\\' | '\'' | '"' => {
-                                    *kp = c;
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+/* Converts a NUL-terminated option string to bytes.  */
+fn option_string(mut p: *const char) -> Vec<u8> {
+    let mut result: Vec<u8> = Vec::new();
+    while (*p != '\0') {
+        result.push(*p as u8);
+        p = p.offset(1);
+    }
+    return result;
+}
 
-                                'n' => {
-                                    *kp = '\n';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+/* Converts a directive argument to the NUL-terminated form stored by
+   Options.  Options live for the whole run, so the storage is leaked.  */
+pub fn option_arg(arg: &[u8]) -> *const char {
+    let mut v: Vec<char> = arg.iter().map(|&b| b as char).collect();
+    v.push('\0');
+    return Box::leak(v.into_boxed_slice()).as_ptr();
+}
 
-                                't' => {
-                                    *kp = '\t';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
 
-                                'r' => {
-                                    *kp = '\r';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+/* Parses a keyword given as a string in ANSI C syntax.  LINE starts with
+   the opening double-quote.  Returns the keyword, the index in LINE just
+   after the closing double-quote, and whether the keyword depends on the
+   execution character set.  */
+fn parse_string_keyword (line: &[u8], lineno: u32) -> (Vec<u8>, usize, bool) {
 
-                                /* The following escape sequences are not allowed yet in Rust 
-                                'f' => {
-                                    *kp = '\f';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+    match scan_string_keyword(line, lineno) {
+        Ok(result) => return result,
+        Err(message) => {
+            eprintln!("{}:{}: {}\n", pretty_input_file_name (), lineno, message);
+            std::process::exit(1);
+        }
+    }
+}
 
-                                'b' => {
-                                    *kp = '\b';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+/* Does the work of parse_string_keyword, returning the message of a fatal
+   error instead of exiting.  Escapes out of range are only warned about
+   and yield their low byte, as with gperf.  */
+fn scan_string_keyword (line: &[u8], lineno: u32) -> Result<(Vec<u8>, usize, bool), &'static str> {
 
-                                'a' => {
-                                    *kp = '\a';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
+    let mut keyword: Vec<u8> = Vec::new();
+    let mut charset_dependent: bool = false;
+    let mut i: usize = 1;
 
-                                'v' => {
-                                    *kp = '\v';
-                                    lp = lp.offset(1);
-                                    charset_dependent = true;
-                                }
-                                */
+    loop {
+        if (i >= line.len() || line[i] == b'\n') {
+            return Err("unterminated string");
+        }
 
-                                _ => {
-                                    eprintln!("{}:{}: invalid escape sequence in string\n",
-                                    pretty_input_file_name (), lineno);
-                                    std::process::exit(1);
-                                }
-                            } 
-
-                        } else if (c == '"') {
-                            break;
-                        } else {
-                            *kp = c;
-                            lp = lp.offset(1);
-                            charset_dependent = true;
-                        }
+        let c: u8 = line[i];
+        if (c == b'\\') {
+            i += 1;
+            let c: u8 = if i < line.len() { line[i] } else { b'\n' };
 
-                        kp = kp.offset(1);
-                    }
+            match c {
+                b'0'..=b'7' => {
+                    let mut code: u32 = 0;
+                    let mut count: i32 = 0;
 
-                lp = lp.offset(1);
-                if (lp < line_end && *lp != '\n') {
-                    let mut itr = delimiters;
-                    let mut is_found = 0;
-                    while (itr != null_mut()) {
-                        if (*itr == *lp) {
-                            is_found = 1;
-                            break;
-                        }
-                        itr = itr.offset(1);
+                    while (count < 3 && i < line.len() && line[i] >= b'0' && line[i] <= b'7') {
+                        code = (code << 3) + (line[i] - b'0') as u32;
+                        i += 1;
+                        count += 1;
                     }
-                    if (is_found == 0) {
-                        fprintf (stderr, "{}:{}: string not followed by delimiter\n",
+
+                    if (code > 255 /*UCHAR_MAX*/) {
+                        eprintln!("{}:{}: octal escape out of range\n",
                         pretty_input_file_name (), lineno);
-                        std::process::exit(1); 
                     }
-                    lp = lp.offset(1);
-                }
-
-                keyword_length = (kp as usize - keyword as usize) / 4;
-                if(option[TYPE]) {
-                    let mut line_rest: *mut char = &mut ['\0'; (line_end as usize - lp as usize) / 4 + 1]; 
-                    copy_nonoverlapping(lp, line_rest, (line_end as usize - lp as usize));
-                    *line_rest.offset((line_end as usize - lp as usize) / 4 - if(line_end > lp && line_end.offset(-1) == '\n') {1} else {0})
-                        = '\0';
-                    rest = line_rest;
-
-                } else {
-                    rest = empty_string;
+                    keyword.push(code as u8);
                 }
-            } else {
-                /* Not a string.  Look for the delimiter.  */
-                let mut lp: *const char = line;
-                loop {
-                    if (!(lp < line_end && *lp != '\n')) {
-                        keyword = line;
-                        keyword_length = (lp as usize - line as usize) / 4;
-                        rest = empty_string;
-                        break;
-                    }
 
-                    let mut itr = delimiters;
-                    let mut is_found = 0;
-                    while (itr != null_mut()) {
-                        if (*itr == *lp) {
-                            is_found = 1;
-                            break;
+                b'x' => {
+                    let mut code: u32 = 0;
+                    let mut count: i32 = 0;
+                    i += 1;
+                    while (i < line.len() && line[i].is_ascii_hexdigit()) {
+                        code = (code << 4) + (line[i] as char).to_digit(16).unwrap();
+                        i += 1;
+                        count += 1;
+                        if (code > 0xffff) {
+                            /* Out of range anyway; avoid overflow.  */
+                            code = 0x100;
                         }
-                        itr = itr.offset(1);
                     }
 
-                    if (is_found == 1) {
-                        keyword = line;
-                        keyword_length = (lp as usize - line as usize) / 4;
-                        lp = lp.offset(1);
-
-                        if (option[TYPE]) {
-                            let mut line_rest: *mut char = &mut ['\0'; (line_end as usize - lp as usize) / 4 + 1] as *mut char; 
-                            copy_nonoverlapping(lp, line_rest, (line_end as usize - lp as usize) / 4);
-                            *line_rest.offset((line_end as usize - lp as usize) / 4 - if(line_end > lp && line_end.offset(-1) == '\n') {1} else {0})
-                                = '\0';
-                            rest = line_rest;
-                        } else {
-                            rest = empty_string;
-                        }
-                        break;
+                    if (count == 0) {
+                        eprintln!("{}:{}: hexadecimal escape without any hex digits\n",
+                                    pretty_input_file_name(), lineno);
                     }
 
-                    lp = lp.offset(1);
+                    if (code > 255) {
+                        eprintln!("{}:{}: hexadecimal escape out of range\n",
+                        pretty_input_file_name (), lineno);
+                    }
+                    keyword.push(code as u8);
+                }
 
+                b'\\' | b'\'' | b'"' => {
+                    keyword.push(c);
+                    i += 1;
+                    charset_dependent = true;
                 }
 
-                if (keyword_length > 0) {
+                b'n' | b't' | b'r' | b'f' | b'b' | b'a' | b'v' => {
+                    keyword.push(match c {
+                        b'n' => b'\n',
+                        b't' => b'\t',
+                        b'r' => b'\r',
+                        b'f' => 0x0C,
+                        b'b' => 0x08,
+                        b'a' => 0x07,
+                        _ => 0x0B
+                    });
+                    i += 1;
                     charset_dependent = true;
                 }
-            }
-                /* Allocate Keyword and add it to the list.  */
-                let mut new_kw: *mut Keyword = *_factory.create_keyword (keyword, keyword_length,
-                                                                            rest);
-                *new_kw._lineno = lineno;
-                *list_tail = &mut Keyword_list::new(new_kw) as *mut Keyword_List;
-                list_tail = &((**list_tail).rest());
-    
-            }
-            lineno += 1;
-            line = line_end;
-        }
 
-        *list_tail = null_mut();
+                _ => {
+                    return Err("invalid escape sequence in string");
+                }
+            }
 
-        if (_head == null_mut()) {
-            eprintln!("{}: No keywords in input file!\n",
-            pretty_input_file_name());
+        } else if (c == b'"') {
+            break;
+        } else {
+            keyword.push(c);
+            i += 1;
+            charset_dependent = true;
         }
-        _charset_dependent = charset_dependent;
     }
 
-    /* To be freed in the destructor.  */
-    _input = input;
-    _input_end = input_end;
-   }
-}
-
-impl Drop for Input {
-    fn drop (&mut self) {
-        std::mem::drop(_return_type);
-        std::mem::drop(_struct_tag);
-        std::mem::drop(_struct_decl);
-        std::mem::drop(input);
-    }
+    return Ok((keyword, i + 1, charset_dependent));
 }
 
 
 fn pretty_input_file_name () -> *const char {
 
-    if option.get_input_file_name() {
-        return option.get_input_file_name();
+    if current_file_name != null() {
+        return current_file_name;
     } else {
         return "(standard input)";
     }
 }
 
 
-fn is_declaration (mut line: *const char, mut line_end: *const char, mut lineno: u32, mut decl: *const char) -> bool {
-
-
-    line = line.offset(1);
-    let mut d: *const char = decl;
+/* Matches the directive name DECL in LINE, starting at index I.
+   Returns the index just after the name, or None.  */
+fn match_declaration_name (line: &[u8], mut i: usize, decl: &str) -> Option<usize> {
 
-    while *d {
+    for d in decl.bytes() {
 
-        if !(line < line_end){
-            return false;
-        } 
+        if !(i < line.len()){
+            return None;
+        }
 
-        if (!(*line == *d || (*d == '-' && *line == '_'))) {
-            return false;
+        if (!(line[i] == d || (d == b'-' && line[i] == b'_'))) {
+            return None;
         }
 
-        line = line.offset(1);
-        
-        d = d.offset(1);
+        i += 1;
+    }
 
+    if (i < line.len() && (line[i].is_ascii_alphabetic() || line[i] == b'-' || line[i] == b'_')) {
+        return None;
     }
 
-    if (line < line_end && ((*line >= 'A' && *line <= 'Z')
-        || (*line >= 'a' && *line <= 'z')
-        || *line == '-' || *line == '_')) {
-            return false;
-        }
+    return Some(i);
+}
 
 
-    while (line < line_end && (*line == ' ' || *line == '\t')) {
-        line = line.offset(1);
+/* Checks that only whitespace follows index I in LINE.  */
+fn check_junk_after_declaration (line: &[u8], mut i: usize, lineno: u32) {
+
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+        i += 1;
     }
 
-    if line < line_end && *line != '\n' {
-        
-        eprintln!("{}:{}: junk after declaration\n", pretty_input_file_name(), line_no);
+    if i < line.len() && line[i] != b'\n' {
+
+        eprintln!("{}:{}: junk after declaration\n", pretty_input_file_name(), lineno);
         std::process::exit(1);
-    
-    }
 
-    return true;
+    }
 }
 
 
-fn is_declaration_with_arg (mut line: *const char, mut line_end: *const char, mut lineno: u32, 
-                            mut lineno: u32, mut decl: *const char, mut argp: *mut *mut argp) -> bool {
-    
-    line = line.offset(1);
+/* Reads an argument, up to the next whitespace, starting at index I.
+   Returns the index just after it.  */
+fn read_declaration_arg (line: &[u8], mut i: usize, argp: &mut Vec<u8>) -> usize {
 
-    let mut d: *const char = decl;
+    argp.clear();
+    while i < line.len() && !(line[i] == b' ' || line[i] == b'\t' || line[i] == b'\n') {
+        argp.push(line[i]);
+        i += 1;
+    }
+    return i;
+}
 
-    while *d {
 
-        if !(line < line_end){
-            return false;
-        } 
+fn is_declaration (line: &[u8], lineno: u32, decl: &str) -> bool {
 
-        if (!(*line == *d || (*d == '-' && *line == '_'))) {
-            return false;
+    match match_declaration_name(line, 1, decl) {
+        None => false,
+        Some(i) => {
+            check_junk_after_declaration(line, i, lineno);
+            true
         }
+    }
+}
 
-        line = line.offset(1);
-        
-        d = d.offset(1);
 
-    }
+fn is_declaration_with_arg (line: &[u8], lineno: u32, decl: &str, argp: &mut Vec<u8>) -> bool {
 
-    if (line < line_end && ((*line >= 'A' && *line <= 'Z')
-    || (*line >= 'a' && *line <= 'z')
-    || *line == '-' || *line == '_')) {
-        return false;
-    } 
+    let mut i: usize = match match_declaration_name(line, 1, decl) {
+        None => return false,
+        Some(i) => i
+    };
 
-    if (!(line < line_end && *line == '-')) {
+    if (!(i < line.len() && line[i] == b'=')) {
         eprintln!("{}:{}: missing argument in %{}=ARG declaration.",
                     pretty_input_file_name(), lineno, decl);
-        std::process::exit(1); 
+        std::process::exit(1);
     }
 
-    line = line.offset(1);
+    i = read_declaration_arg(line, i + 1, argp);
+    check_junk_after_declaration(line, i, lineno);
+    return true;
+}
 
-    let mut arg_arr: [char; line_end - line + 1];
-    let mut arg: *mut char = &arg_arr as *mut char;
-    let mut p: *mut char = arg;
 
-    while line < line_end && !(*line == ' ' || *line == '\t' || *line == '\n') {
-        *p = *line;
+/* Recognizes %include "FILE" in the keywords section.  */
+fn is_include_declaration (line: &[u8], lineno: u32, argp: &mut Vec<u8>) -> bool {
 
-        line = line.offset(1);
-        p = p.offset(1);
+    if (line[0] != b'%') {
+        return false;
     }
 
-    *p = '\0';
+    let mut i: usize = match match_declaration_name(line, 1, "include") {
+        None => return false,
+        Some(i) => i
+    };
 
-    while (line < line_end && (*line == ' ' || *line == '\t')) {
-        line = line.offset(1);
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+        i += 1;
     }
 
-    if (line < line_end && *line != '\n') {
-        eprintln!("{}:{}: junk after declaration\n", pretty_input_file_name(), lineno);
+    if (!(i < line.len() && line[i] == b'"')) {
+        eprintln!("{}:{}: missing file name in %include \"FILE\" declaration.",
+                    pretty_input_file_name(), lineno);
+        std::process::exit(1);
+    }
+    i += 1;
+
+    argp.clear();
+    while (i < line.len() && line[i] != b'"' && line[i] != b'\n') {
+        argp.push(line[i]);
+        i += 1;
+    }
+
+    if (!(i < line.len() && line[i] == b'"') || argp.is_empty()) {
+        eprintln!("{}:{}: unterminated file name in %include declaration.",
+                    pretty_input_file_name(), lineno);
         std::process::exit(1);
     }
 
-    *argp = arg;
+    check_junk_after_declaration(line, i + 1, lineno);
     return true;
 }
 
-/* 
-*/
-
 
-fn is_define_declaration (mut line: *const char, mut line_end: *const char, mut lineno: u32,
-                                mut decl: *const char, mut argp: *mut *mut argp) -> bool {
+/* Recognizes %char-equiv "CHARS", with CHARS in ANSI C string syntax.  */
+fn is_char_equiv_declaration (line: &[u8], lineno: u32, argp: &mut Vec<u8>) -> bool {
 
-    line = line.offset(1);
+    let mut i: usize = match match_declaration_name(line, 1, "char-equiv") {
+        None => return false,
+        Some(i) => i
+    };
 
-    //CHECK THIS SCOPE'S CODE
-    {
-         for d in "define".chars()
-         {
-            if (!(line < line_end)) {
-                return false;
-            }
-            
-            if (!(*line == d)) {
-                return false;
-            }
-          
-           line = line.offset(1);
-        }
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+        i += 1;
+    }
 
-        if (!(line < line_end && (*line == ' ' || *line == '\t'))) {
-            return false;
-        }
+    if (!(i < line.len() && line[i] == b'"')) {
+        eprintln!("{}:{}: missing string in %char-equiv \"CHARS\" declaration.",
+                    pretty_input_file_name(), lineno);
+        std::process::exit(1);
     }
 
-    while (line < line_end && (*line == ' ' || *line == '\t')) {
-       line = line.offset(1);
+    let (chars, end, _) = parse_string_keyword(&line[i..], lineno);
+    *argp = chars;
+    check_junk_after_declaration(line, i + end, lineno);
+    return true;
+}
+
+
+/* Recognizes %set NAME.  */
+fn is_set_declaration (line: &[u8], lineno: u32, argp: &mut Vec<u8>) -> bool {
+
+    if (line[0] != b'%') {
+        return false;
     }
 
-    let mut d: *const char = decl;
-    
-    while *d {
+    let mut i: usize = match match_declaration_name(line, 1, "set") {
+        None => return false,
+        Some(i) => i
+    };
 
-        if (!(line < line_end)) {
-            return false;
-        }
-        
-        if (!(*line == *d || (*d == '-' && *line == '_'))) {
-            return false;
-        }
-        
-        line = line.offset(1);
-        d = d.offset(1);
+    if (!(i < line.len() && (line[i] == b' ' || line[i] == b'\t'))) {
+        eprintln!("{}:{}: missing name in %set NAME declaration.",
+                    pretty_input_file_name(), lineno);
+        std::process::exit(1);
     }
 
-    if (line < line_end
-        && ((*line >= 'A' && *line <= 'Z')
-            || (*line >= 'a' && *line <= 'z')
-            || *line == '-' || *line == '_')) {
-                return false;
-            }
-    
-            if (!(line < line_end && (*line == ' ' || *line == '\t'))) {
-                eprintln!("{}:{}: missing argument in %define {} ARG declaration.", 
-                            pretty_input_file_name(), lineno, decl);
-                std::process::exit(1);
-            }
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+        i += 1;
+    }
 
-    loop {
-        line = line.offset(1);
-        if line < line_end && (*line == ' ' || *line == '\t') {
-            break;
-        }
+    i = read_declaration_arg(line, i, argp);
+    if (argp.is_empty()) {
+        eprintln!("{}:{}: missing name in %set NAME declaration.",
+                    pretty_input_file_name(), lineno);
+        std::process::exit(1);
     }
+    check_junk_after_declaration(line, i, lineno);
+    return true;
+}
 
-    let mut arg_temp: [char; line_end - line + 1];
-    let mut arg: *mut char = &arg_temp as *mut char;
-    let mut p: *mut char = arg;
 
-    while (line < line_end && !(*line == ' ' || *line == '\t' || *line == '\n')) {
-        *p = *line;
+fn is_define_declaration (line: &[u8], lineno: u32, decl: &str, argp: &mut Vec<u8>) -> bool {
 
-        line = line.offset(1);
-        p = p.offset(1);
+    if (!line[1..].starts_with(b"define")) {
+        return false;
+    }
+    let mut i: usize = 7;
+
+    if (!(i < line.len() && (line[i] == b' ' || line[i] == b'\t'))) {
+        return false;
+    }
+
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+       i += 1;
     }
 
-    
-    *p = '\0'; 
+    i = match match_declaration_name(line, i, decl) {
+        None => return false,
+        Some(i) => i
+    };
 
-    while (line < line_end && (*line == ' ' || *line == '\t')) {
-        line = line.offset(1);
+    if (!(i < line.len() && (line[i] == b' ' || line[i] == b'\t'))) {
+        eprintln!("{}:{}: missing argument in %define {} ARG declaration.",
+                    pretty_input_file_name(), lineno, decl);
+        std::process::exit(1);
     }
-    
-    if (line < line_end && *line != '\n') {
-      eprintln!("{}:{}: junk after declaration\n",
-               pretty_input_file_name (), lineno);
-      std::process::exit(1);
+
+    while (i < line.len() && (line[i] == b' ' || line[i] == b'\t')) {
+        i += 1;
     }
 
-    *argp = arg;
+    i = read_declaration_arg(line, i, argp);
+    check_junk_after_declaration(line, i, lineno);
     return true;
 }
 
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn every_byte_escapes() {
+        for b in 0..=255u8 {
+            assert_eq!(scan_string_keyword(format!("\"\\{:o}\"", b).as_bytes(), 1).unwrap().0, vec![b]);
+            assert_eq!(scan_string_keyword(format!("\"\\x{:02X}\"", b).as_bytes(), 1).unwrap().0, vec![b]);
+        }
+    }
+
+    #[test]
+    fn truncated_strings_are_rejected() {
+        let line: &[u8] = b"\"a\\n\\101\\x4a\\\"b\"";
+        for len in 1..line.len() {
+            assert!(scan_string_keyword(&line[..len], 1).is_err());
+        }
+        assert_eq!(scan_string_keyword(line, 1).unwrap().0, b"a\nAJ\"b".to_vec());
+        assert_eq!(scan_string_keyword(b"\"abc\ndef\"", 1), Err("unterminated string"));
+        assert_eq!(scan_string_keyword(b"\"a\\q\"", 1), Err("invalid escape sequence in string"));
+    }
+
+    #[test]
+    fn escape_ranges() {
+        /* At most three octal digits.  */
+        assert_eq!(scan_string_keyword(b"\"\\1234\"", 1).unwrap().0, vec![0o123, b'4']);
+        assert_eq!(scan_string_keyword(b"\"\\08\"", 1).unwrap().0, vec![0, b'8']);
+        assert_eq!(scan_string_keyword(b"\"\\377\"", 1).unwrap().0, vec![255]);
+        /* Out of range: only a warning, and the low byte.  */
+        assert_eq!(scan_string_keyword(b"\"\\400\"", 1).unwrap().0, vec![0]);
+        /* Hex escapes take every hex digit.  */
+        assert_eq!(scan_string_keyword(b"\"\\x0041g\"", 1).unwrap().0, vec![0x41, b'g']);
+        assert_eq!(scan_string_keyword(b"\"\\xfff\"", 1).unwrap().0, vec![0xff]);
+        assert_eq!(scan_string_keyword(b"\"\\x\"", 1).unwrap().0, vec![0]);
+    }
+
+    #[test]
+    fn charset_dependence() {
+        assert_eq!(scan_string_keyword(b"\"\\101\\x42\"", 1).unwrap().2, false);
+        assert_eq!(scan_string_keyword(b"\"\\101B\"", 1).unwrap().2, true);
+        assert_eq!(scan_string_keyword(b"\"\\n\"", 1).unwrap().2, true);
+    }
+
+}
@@ -1,4 +1,5 @@
 mod positions;
+mod target_charset;
 use std::process;
 
 
@@ -22,9 +23,14 @@ fn sort_char_set(base: *mut u32, len: i32) {
 
 /* Declaring Keyword */
 struct Keyword{
-    _allchars: String,
+    _allchars: Vec<u8>,
     _allchars_length: i32,
-    _rest: String,
+    /* The bytes after the keyword, as in the input file.  */
+    _rest: Vec<u8>,
+    /* _rest split into struct initializers, with %struct-type.  */
+    _initializers: Vec<String>,
+    /* Where the keyword was defined: input file and line number.  */
+    _file_name: *const char,
     _lineno: u32
 }
 
@@ -34,6 +40,10 @@ struct KeywordExt{
     _selchars: *const u32,
     _selchars_length: i32,
     _duplicate_link: *mut KeywordExt,
+    /* The hash value, computed by the search.  */
+    _hash_value: i32,
+    /* The index of the keyword in the word list, assigned by the output.  */
+    _final_index: i32,
 }
 
 
@@ -51,9 +61,9 @@ impl KeywordExt{
             let mut c: u32;
             
             if i == Positions::LASTCHAR {
-                c = _allchars[_allchars_length - 1] as u8;
+                c = to_target(_allchars[_allchars_length - 1] as u8) as u32;
             } else if i < _allchars_length {
-                c = _allchars[i] as u8;
+                c = to_target(_allchars[i] as u8) as u32;
                 if alpha_inc {
                     c += alpha_inc[i];
                 }
@@ -61,15 +71,17 @@ impl KeywordExt{
             } else {
 
                 process::abort();
-                if alpha_unify {
-                    c = alpha_unify[c];
-                }
 
-                *ptr = c;
-                ptr += 1;
-            
             }
 
+            /* Case and %char-equiv folding, from compute_alpha_unify.  */
+            if alpha_unify {
+                c = alpha_unify[c];
+            }
+
+            *ptr = c;
+            ptr += 1;
+
         }
 
         _selchars = key_set;
@@ -106,4 +118,21 @@ struct Keyword_Factory {
 
 }
 
+impl Keyword_Factory {
+
+    /* Creates a new Keyword.  */
+    pub fn create_keyword(&mut self, allchars: Vec<u8>, rest: Vec<u8>) -> *mut Keyword {
+        let allchars_length: i32 = allchars.len() as i32;
+        Box::into_raw(Box::new(Keyword {
+            _allchars: allchars,
+            _allchars_length: allchars_length,
+            _rest: rest,
+            _initializers: Vec::new(),
+            _file_name: std::ptr::null(),
+            _lineno: 0
+        }))
+    }
+
+}
+
 static empty_string: [char; 1];
\ No newline at end of file
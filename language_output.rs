@@ -0,0 +1,599 @@
+mod char_equiv;
+mod compare_function;
+mod model;
+mod options;
+mod reverse_table;
+mod struct_fields;
+
+use std::process;
+
+/* Generates the lookup code in Go, Java or Python from a model: the hash
+   function, the word list and the struct payloads, named after the
+   --hash-function-name, --lookup-function-name and --word-array-name
+   settings in the style of the language.  The word list is sorted by hash
+   value; an offset table gives the entries of each hash value, so that
+   duplicates (-D) need no special code.  */
+struct Language_Output<'a> {
+    _model: &'a Model,
+
+    /* The fields of the struct declaration; the first one holds the
+       keyword.  Empty without --struct-type.  */
+    _fields: &'a Vec<Struct_Field>,
+
+    /* The keywords, sorted by hash value.  */
+    _entries: Vec<&'a Model_Keyword>,
+
+    /* For each hash value h, the entries with that value are those from
+       _offsets[h] to _offsets[h + 1].  */
+    _offsets: Vec<usize>,
+
+    /* The associated values, with the case unification folded in.  */
+    _asso_values: Vec<i32>,
+
+    _min_key_len: usize,
+    _max_key_len: usize
+}
+
+/* Splits an identifier at underscores and case changes.  */
+pub fn identifier_words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    let mut previous_lower: bool = false;
+    for c in name.chars() {
+        if (c == '_' || c == '-') {
+            if (!current.is_empty()) {
+                words.push(current.to_lowercase());
+                current.clear();
+            }
+            previous_lower = false;
+            continue;
+        }
+        if (c.is_ascii_uppercase() && previous_lower && !current.is_empty()) {
+            words.push(current.to_lowercase());
+            current.clear();
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+    if (!current.is_empty()) {
+        words.push(current.to_lowercase());
+    }
+    return words;
+}
+
+/* "in_word_set" -> "InWordSet".  */
+pub fn pascal_case(name: &str) -> String {
+    return identifier_words(name).iter()
+        .map(|w| { let mut cs = w.chars(); match cs.next() { Some(f) => f.to_uppercase().chain(cs).collect(), None => String::new() } })
+        .collect();
+}
+
+/* "in_word_set" -> "inWordSet".  */
+pub fn camel_case(name: &str) -> String {
+    let pascal: String = pascal_case(name);
+    let mut cs = pascal.chars();
+    return match cs.next() { Some(f) => f.to_lowercase().chain(cs).collect(), None => String::new() };
+}
+
+/* Decodes one or more adjacent C string literals.  Returns None if TEXT
+   is not made of string literals.  */
+fn c_string_value(text: &str) -> Option<Vec<u8>> {
+    let bytes: &[u8] = text.trim().as_bytes();
+    let mut result: Vec<u8> = Vec::new();
+    let mut i: usize = 0;
+    if (bytes.is_empty()) {
+        return None;
+    }
+    while (i < bytes.len()) {
+        if (bytes[i].is_ascii_whitespace()) {
+            i += 1;
+            continue;
+        }
+        if (bytes[i] != b'"') {
+            return None;
+        }
+        i += 1;
+        loop {
+            if (i >= bytes.len()) {
+                return None;
+            }
+            let c: u8 = bytes[i];
+            i += 1;
+            if (c == b'"') {
+                break;
+            }
+            if (c != b'\\') {
+                result.push(c);
+                continue;
+            }
+            if (i >= bytes.len()) {
+                return None;
+            }
+            let e: u8 = bytes[i];
+            i += 1;
+            match e {
+                b'n' => result.push(b'\n'),
+                b't' => result.push(b'\t'),
+                b'r' => result.push(b'\r'),
+                b'f' => result.push(0x0c),
+                b'v' => result.push(0x0b),
+                b'a' => result.push(0x07),
+                b'b' => result.push(0x08),
+                b'0'..=b'7' => {
+                    let mut value: u32 = (e - b'0') as u32;
+                    let mut count: usize = 1;
+                    while (count < 3 && i < bytes.len() && bytes[i] >= b'0' && bytes[i] <= b'7') {
+                        value = value * 8 + (bytes[i] - b'0') as u32;
+                        i += 1;
+                        count += 1;
+                    }
+                    result.push(value as u8);
+                }
+                b'x' => {
+                    let mut value: u32 = 0;
+                    while (i < bytes.len() && bytes[i].is_ascii_hexdigit()) {
+                        value = value * 16 + (bytes[i] as char).to_digit(16).unwrap();
+                        i += 1;
+                    }
+                    result.push(value as u8);
+                }
+                _ => result.push(e)
+            }
+        }
+    }
+    return Some(result);
+}
+
+impl<'a> Language_Output<'a> {
+
+    pub fn new(model: &'a Model, fields: &'a Vec<Struct_Field>) -> Language_Output<'a> {
+        if (option[TYPE] && fields.is_empty()) {
+            eprintln!("{}: --struct-type output in {} needs the struct declaration with its fields\n",
+                      program_name, if option[GO] { "Go" } else if option[JAVA] { "Java" } else { "Python" });
+            process::exit(1);
+        }
+
+        let mut entries: Vec<&'a Model_Keyword> = model.get_keywords().iter().collect();
+        entries.sort_by_key(|k| (k._hash_value, k._final_index));
+
+        let table_size: usize = entries.last().map_or(0, |k| k._hash_value as usize + 1);
+        let mut offsets: Vec<usize> = Vec::new();
+        let mut next: usize = 0;
+        for h in 0..table_size + 1 {
+            while (next < entries.len() && (entries[next]._hash_value as usize) < h) {
+                next += 1;
+            }
+            offsets.push(next);
+        }
+
+        let asso_values: Vec<i32> = if model.get_alpha_unify().is_empty() {
+            model.get_asso_values().clone()
+        } else {
+            model.get_alpha_unify().iter().map(|&c| model.get_asso_values()[c as usize]).collect()
+        };
+
+        Language_Output {
+            _model: model,
+            _fields: fields,
+            _min_key_len: entries.iter().map(|k| k._allchars.len()).min().unwrap_or(0),
+            _max_key_len: entries.iter().map(|k| k._allchars.len()).max().unwrap_or(0),
+            _entries: entries,
+            _offsets: offsets,
+            _asso_values: asso_values
+        }
+    }
+
+    /* Writes BYTES as a string literal of the output language.  Go
+       strings hold bytes; Java and Python strings hold characters, so the
+       keyword must be UTF-8 there.  Control characters are written as
+       simple or octal escapes, since Java translates \u escapes before
+       it reads the literal: "\u000a" would end the line.  */
+    pub fn string_literal(&self, bytes: &[u8]) -> String {
+        let mut result: String = String::from("\"");
+        if (option[GO]) {
+            for &b in bytes {
+                match b {
+                    b'"' | b'\\' => { result.push('\\'); result.push(b as char); }
+                    0x20..=0x7e => result.push(b as char),
+                    _ => result.push_str(&format!("\\x{:02x}", b))
+                }
+            }
+        } else {
+            let text: &str = match std::str::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => {
+                    eprintln!("{}: \"{}\" is not valid UTF-8, which {} strings require\n",
+                              program_name, String::from_utf8_lossy(bytes),
+                              if option[JAVA] { "Java" } else { "Python" });
+                    process::exit(1);
+                }
+            };
+            for c in text.chars() {
+                match c {
+                    '"' | '\\' => { result.push('\\'); result.push(c); }
+                    ' '..='~' => result.push(c),
+                    '\n' => result.push_str("\\n"),
+                    '\r' => result.push_str("\\r"),
+                    '\t' => result.push_str("\\t"),
+                    '\0'..='\u{7f}' => result.push_str(&format!("\\{:03o}", c as u32)),
+                    _ if (c as u32) < 0x10000 => result.push_str(&format!("\\u{:04x}", c as u32)),
+                    _ if option[PYTHON] => result.push_str(&format!("\\U{:08x}", c as u32)),
+                    _ => {
+                        /* A surrogate pair.  */
+                        let mut units: [u16; 2] = [0; 2];
+                        for unit in c.encode_utf16(&mut units).iter() {
+                            result.push_str(&format!("\\u{:04x}", unit));
+                        }
+                    }
+                }
+            }
+        }
+        result.push('"');
+        return result;
+    }
+
+    /* Returns the type of FIELD in the output language.  Integer, floating
+       and string fields map to the language's types; other fields hold the
+       C source text of their initializers.  */
+    fn field_type(&self, field: &Struct_Field) -> &'static str {
+        match field._kind {
+            Field_Kind::INTEGER => if option[PYTHON] { "int" } else if option[GO] { "int64" } else { "long" },
+            Field_Kind::FLOATING => if option[GO] { "float64" } else if option[JAVA] { "double" } else { "float" },
+            _ => if option[JAVA] { "String" } else if option[GO] { "string" } else { "str" }
+        }
+    }
+
+    /* Converts a C initializer of FIELD to the output language.  Integer
+       literals become decimal, with an L in Java when they do not fit an
+       int; symbolic constants are kept, and must be defined in the output
+       language as well.  */
+    fn field_value(&self, field: &Struct_Field, initializer: &str) -> String {
+        let initializer: &str = initializer.trim();
+        match field._kind {
+            Field_Kind::INTEGER => {
+                if let Some(value) = parse_integer_literal(initializer) {
+                    if (option[JAVA] && i32::try_from(value).is_err()) {
+                        return format!("{}L", value);
+                    }
+                    return value.to_string();
+                }
+                if let Some(bytes) = c_string_value(&initializer.replace('\'', "\"")) {
+                    if (initializer.starts_with('\'') && bytes.len() == 1) {
+                        return bytes[0].to_string();
+                    }
+                }
+                return String::from(initializer);
+            }
+            Field_Kind::FLOATING => {
+                return String::from(initializer.trim_end_matches(|c| c == 'f' || c == 'F' || c == 'l' || c == 'L'));
+            }
+            Field_Kind::STRING => {
+                if (initializer == "0" || initializer == "NULL") {
+                    return String::from(if option[GO] { "\"\"" } else if option[JAVA] { "null" } else { "None" });
+                }
+                return match c_string_value(initializer) {
+                    Some(bytes) => self.string_literal(&bytes),
+                    None => String::from(initializer)
+                };
+            }
+            _ => self.string_literal(initializer.as_bytes())
+        }
+    }
+
+    /* Returns the output language values of the payload fields, given by
+       REST, the initializers after the keyword, in declaration order.  The
+       fields without an initializer are zero, as in C.  */
+    pub fn payload(&self, rest: &[u8]) -> Vec<String> {
+        if (self._fields.len() < 2) {
+            return Vec::new();
+        }
+        let initializers: Vec<String> = split_initializers(&String::from_utf8_lossy(rest));
+        return self._fields[1..].iter().enumerate()
+                   .map(|(i, field)| self.field_value(field, initializers.get(i).map_or("0", |s| s.as_str())))
+                   .collect();
+    }
+
+    /* Returns the hash function body lines, as expressions added to the
+       hash value, given how the language indexes the key bytes.  */
+    fn hash_terms(&self, byte: &dyn Fn(&str) -> String) -> Vec<(String, String)> {
+        let mut terms: Vec<(String, String)> = Vec::new();
+        for &pos in self._model.get_key_positions().iter() {
+            if (pos == Positions::LASTCHAR) {
+                terms.push((String::new(), byte("n - 1")));
+            } else {
+                let inc: u32 = if (pos as usize) < self._model.get_alpha_inc().len() {
+                    self._model.get_alpha_inc()[pos as usize]
+                } else {
+                    0
+                };
+                let index: String = if inc > 0 { format!("{} + {}", byte(&pos.to_string()), inc) } else { byte(&pos.to_string()) };
+                terms.push((format!("{}", pos + 1), index));
+            }
+        }
+        return terms;
+    }
+
+    /* Returns the fold table of %char-equiv, for the comparison.  */
+    fn fold_values() -> Vec<String> {
+        return char_fold_map().iter().map(|v| v.to_string()).collect();
+    }
+
+    fn integer_list(values: &[String], indent: &str) -> String {
+        let mut result: String = String::new();
+        for (i, value) in values.iter().enumerate() {
+            if (i % 16 == 0) {
+                result.push_str(if i == 0 { "" } else { "\n" });
+                result.push_str(indent);
+            } else {
+                result.push(' ');
+            }
+            result.push_str(value);
+            result.push(',');
+        }
+        return result;
+    }
+
+    fn output_go(&self) {
+        let package: String = identifier_words(&option_text(option.get_class_name())).concat();
+        let lookup: String = pascal_case(&option_text(option.get_function_name()));
+        let hash: String = camel_case(&option_text(option.get_hash_name()));
+        let wordlist: String = camel_case(&option_text(option.get_wordlist_name()));
+
+        print!("// Code generated by gperf; DO NOT EDIT.\n\npackage {}\n\n", package);
+        if (has_char_equivs()) {
+            print!("var foldTable = [256]byte{{\n{}\n}}\n\n", Language_Output::integer_list(&Language_Output::fold_values(), "\t"));
+            print!("func equalFold(a, b string) bool {{\n\tif len(a) != len(b) {{\n\t\treturn false\n\t}}\n\tfor i := 0; i < len(a); i++ {{\n\t\tif foldTable[a[i]] != foldTable[b[i]] {{\n\t\t\treturn false\n\t\t}}\n\t}}\n\treturn true\n}}\n\n");
+        } else if (option[UPPERLOWER]) {
+            print!("func equalFoldASCII(a, b string) bool {{\n\tif len(a) != len(b) {{\n\t\treturn false\n\t}}\n\tfor i := 0; i < len(a); i++ {{\n\t\tx, y := a[i], b[i]\n\t\tif 'A' <= x && x <= 'Z' {{\n\t\t\tx += 'a' - 'A'\n\t\t}}\n\t\tif 'A' <= y && y <= 'Z' {{\n\t\t\ty += 'a' - 'A'\n\t\t}}\n\t\tif x != y {{\n\t\t\treturn false\n\t\t}}\n\t}}\n\treturn true\n}}\n\n");
+        }
+
+        let asso: Vec<String> = self._asso_values.iter().map(|v| v.to_string()).collect();
+        print!("var {}AssoValues = [...]int{{\n{}\n}}\n\n", hash, Language_Output::integer_list(&asso, "\t"));
+
+        print!("func {}(s string) int {{\n\tn := len(s)\n\thval := {}\n", hash, if option[NOLENGTH] { "0" } else { "n" });
+        for (min_len, index) in self.hash_terms(&|i| format!("int(s[{}])", i)) {
+            if (min_len.is_empty()) {
+                print!("\thval += {}AssoValues[{}]\n", hash, index);
+            } else {
+                print!("\tif n >= {} {{\n\t\thval += {}AssoValues[{}]\n\t}}\n", min_len, hash, index);
+            }
+        }
+        print!("\treturn hval\n}}\n\n");
+
+        if (option[TYPE]) {
+            print!("type Entry struct {{\n");
+            for (i, field) in self._fields.iter().enumerate() {
+                print!("\t{} {}\n", pascal_case(&field._name), if i == 0 { "string" } else { self.field_type(field) });
+            }
+            print!("}}\n\nvar {} = [...]Entry{{\n", wordlist);
+            for keyword in self._entries.iter() {
+                let mut values: Vec<String> = vec![self.string_literal(&keyword._allchars)];
+                values.extend(self.payload(&keyword._rest));
+                print!("\t{{{}}},\n", values.join(", "));
+            }
+        } else {
+            print!("var {} = [...]string{{\n", wordlist);
+            for keyword in self._entries.iter() {
+                print!("\t{},\n", self.string_literal(&keyword._allchars));
+            }
+        }
+        print!("}}\n\n");
+
+        let offsets: Vec<String> = self._offsets.iter().map(|v| v.to_string()).collect();
+        print!("var {}Offsets = [...]int{{\n{}\n}}\n\n", wordlist, Language_Output::integer_list(&offsets, "\t"));
+
+        let key: String = if option[TYPE] { format!(".{}", pascal_case(&self._fields[0]._name)) } else { String::new() };
+        let compare: String = if let Some(name) = compare_function() {
+            print!("// {} is supplied by the user and returns 0 if keyword matches s.\nvar _ func(keyword, s string) int = {}\n\n", name, name);
+            format!("{}({}[i]{}, s) == 0", name, wordlist, key)
+        } else if has_char_equivs() {
+            format!("equalFold(s, {}[i]{})", wordlist, key)
+        } else if option[UPPERLOWER] {
+            format!("equalFoldASCII(s, {}[i]{})", wordlist, key)
+        } else {
+            format!("s == {}[i]{}", wordlist, key)
+        };
+        if (option[TYPE]) {
+            print!("// {} returns the entry of keyword s, if s is a keyword.\nfunc {}(s string) (*Entry, bool) {{\n", lookup, lookup);
+        } else {
+            print!("// {} reports whether s is a keyword.\nfunc {}(s string) bool {{\n", lookup, lookup);
+        }
+        let not_found: &str = if option[TYPE] { "nil, false" } else { "false" };
+        print!("\tif len(s) < {} || len(s) > {} {{\n\t\treturn {}\n\t}}\n", self._min_key_len, self._max_key_len, not_found);
+        if (option[SEVENBIT]) {
+            /* The associated values only cover 7-bit characters.  */
+            print!("\tfor i := 0; i < len(s); i++ {{\n\t\tif s[i] >= 0x80 {{\n\t\t\treturn {}\n\t\t}}\n\t}}\n", not_found);
+        }
+        print!("\th := {}(s)\n\tif h < 0 || h >= len({}Offsets)-1 {{\n\t\treturn {}\n\t}}\n", hash, wordlist, not_found);
+        print!("\tfor i := {}Offsets[h]; i < {}Offsets[h+1]; i++ {{\n\t\tif {} {{\n", wordlist, wordlist, compare);
+        print!("\t\t\treturn {}\n\t\t}}\n\t}}\n\treturn {}\n}}\n", if option[TYPE] { format!("&{}[i], true", wordlist) } else { String::from("true") }, not_found);
+    }
+
+    fn output_java(&self) {
+        let class_name: String = pascal_case(&option_text(option.get_class_name()));
+        let lookup: String = camel_case(&option_text(option.get_function_name()));
+        let hash: String = camel_case(&option_text(option.get_hash_name()));
+        let wordlist: String = camel_case(&option_text(option.get_wordlist_name()));
+
+        print!("/* Generated by gperf.  Do not edit.  */\n\n");
+        print!("import java.nio.charset.StandardCharsets;\n\n");
+        print!("public final class {} {{\n\n    private {}() {{\n    }}\n\n", class_name, class_name);
+
+        if (option[TYPE]) {
+            print!("    public static final class Entry {{\n");
+            for (i, field) in self._fields.iter().enumerate() {
+                print!("        public final {} {};\n", if i == 0 { "String" } else { self.field_type(field) },
+                       camel_case(&field._name));
+            }
+            let parameters: Vec<String> = self._fields.iter().enumerate()
+                .map(|(i, f)| format!("{} {}", if i == 0 { "String" } else { self.field_type(f) }, camel_case(&f._name)))
+                .collect();
+            print!("\n        Entry({}) {{\n", parameters.join(", "));
+            for field in self._fields.iter() {
+                print!("            this.{} = {};\n", camel_case(&field._name), camel_case(&field._name));
+            }
+            print!("        }}\n    }}\n\n");
+        }
+
+        let asso: Vec<String> = self._asso_values.iter().map(|v| v.to_string()).collect();
+        print!("    private static final int[] {}AssoValues = {{\n{}\n    }};\n\n", hash,
+               Language_Output::integer_list(&asso, "        "));
+
+        print!("    private static int {}(byte[] s) {{\n        int n = s.length;\n        int hval = {};\n",
+               hash, if option[NOLENGTH] { "0" } else { "n" });
+        for (min_len, index) in self.hash_terms(&|i| format!("(s[{}] & 0xff)", i)) {
+            if (min_len.is_empty()) {
+                print!("        hval += {}AssoValues[{}];\n", hash, index);
+            } else {
+                print!("        if (n >= {}) {{\n            hval += {}AssoValues[{}];\n        }}\n", min_len, hash, index);
+            }
+        }
+        print!("        return hval;\n    }}\n\n");
+
+        let element: &str = if option[TYPE] { "Entry" } else { "String" };
+        print!("    private static final {}[] {} = {{\n", element, wordlist);
+        for keyword in self._entries.iter() {
+            if (option[TYPE]) {
+                let mut values: Vec<String> = vec![self.string_literal(&keyword._allchars)];
+                values.extend(self.payload(&keyword._rest));
+                print!("        new Entry({}),\n", values.join(", "));
+            } else {
+                print!("        {},\n", self.string_literal(&keyword._allchars));
+            }
+        }
+        print!("    }};\n\n");
+
+        let offsets: Vec<String> = self._offsets.iter().map(|v| v.to_string()).collect();
+        print!("    private static final int[] {}Offsets = {{\n{}\n    }};\n\n", wordlist,
+               Language_Output::integer_list(&offsets, "        "));
+
+        if (has_char_equivs()) {
+            print!("    private static final int[] FOLD_TABLE = {{\n{}\n    }};\n\n",
+                   Language_Output::integer_list(&Language_Output::fold_values(), "        "));
+            print!("    private static boolean equalsFold(byte[] a, byte[] b) {{\n        if (a.length != b.length) {{\n            return false;\n        }}\n        for (int i = 0; i < a.length; i++) {{\n            if (FOLD_TABLE[a[i] & 0xff] != FOLD_TABLE[b[i] & 0xff]) {{\n                return false;\n            }}\n        }}\n        return true;\n    }}\n\n");
+        } else if (option[UPPERLOWER]) {
+            print!("    private static boolean equalsIgnoreCaseAscii(String a, String b) {{\n        if (a.length() != b.length()) {{\n            return false;\n        }}\n        for (int i = 0; i < a.length(); i++) {{\n            char x = a.charAt(i);\n            char y = b.charAt(i);\n            if (x >= 'A' && x <= 'Z') {{\n                x += 'a' - 'A';\n            }}\n            if (y >= 'A' && y <= 'Z') {{\n                y += 'a' - 'A';\n            }}\n            if (x != y) {{\n                return false;\n            }}\n        }}\n        return true;\n    }}\n\n");
+        }
+
+        let key: String = if option[TYPE] { format!(".{}", camel_case(&self._fields[0]._name)) } else { String::new() };
+        let compare: String = if let Some(name) = compare_function() {
+            print!("    /** Supplied by the user before the first lookup: returns 0 if keyword matches s.  */\n");
+            print!("    public static java.util.function.ToIntBiFunction<String, String> {};\n\n", name);
+            format!("{}.applyAsInt({}[i]{}, s) == 0", name, wordlist, key)
+        } else if has_char_equivs() {
+            format!("equalsFold(bytes, {}[i]{}.getBytes(StandardCharsets.UTF_8))", wordlist, key)
+        } else if option[UPPERLOWER] {
+            format!("equalsIgnoreCaseAscii(s, {}[i]{})", wordlist, key)
+        } else {
+            format!("s.equals({}[i]{})", wordlist, key)
+        };
+        let (result_type, found, not_found) = if option[TYPE] {
+            ("Entry", format!("{}[i]", wordlist), "null")
+        } else {
+            ("boolean", String::from("true"), "false")
+        };
+        print!("    /** Returns {} */\n", if option[TYPE] { "the entry of keyword s, or null if s is not a keyword." } else { "true if s is a keyword." });
+        print!("    public static {} {}(String s) {{\n", result_type, lookup);
+        if let Some(name) = compare_function() {
+            print!("        if ({} == null) {{\n            throw new IllegalStateException(\"{} is not set\");\n        }}\n", name, name);
+        }
+        print!("        byte[] bytes = s.getBytes(StandardCharsets.UTF_8);\n");
+        print!("        if (bytes.length < {} || bytes.length > {}) {{\n            return {};\n        }}\n",
+               self._min_key_len, self._max_key_len, not_found);
+        if (option[SEVENBIT]) {
+            /* The associated values only cover 7-bit characters.  */
+            print!("        for (byte b : bytes) {{\n            if (b < 0) {{\n                return {};\n            }}\n        }}\n", not_found);
+        }
+        print!("        int h = {}(bytes);\n        if (h < 0 || h >= {}Offsets.length - 1) {{\n            return {};\n        }}\n",
+               hash, wordlist, not_found);
+        print!("        for (int i = {}Offsets[h]; i < {}Offsets[h + 1]; i++) {{\n            if ({}) {{\n                return {};\n            }}\n        }}\n",
+               wordlist, wordlist, compare, found);
+        print!("        return {};\n    }}\n}}\n", not_found);
+    }
+
+    fn output_python(&self) {
+        let lookup: String = option_text(option.get_function_name());
+        let hash: String = format!("_{}", option_text(option.get_hash_name()));
+        let wordlist: String = format!("_{}", option_text(option.get_wordlist_name()).to_uppercase());
+
+        print!("# Generated by gperf.  Do not edit.\n\n");
+
+        let asso: Vec<String> = self._asso_values.iter().map(|v| v.to_string()).collect();
+        print!("{}_ASSO_VALUES = (\n{}\n)\n\n\n", hash.to_uppercase(), Language_Output::integer_list(&asso, "    "));
+
+        print!("def {}(s):\n    n = len(s)\n    hval = {}\n", hash, if option[NOLENGTH] { "0" } else { "n" });
+        for (min_len, index) in self.hash_terms(&|i| if i == "n - 1" { String::from("s[-1]") } else { format!("s[{}]", i) }) {
+            if (min_len.is_empty()) {
+                print!("    hval += {}_ASSO_VALUES[{}]\n", hash.to_uppercase(), index);
+            } else {
+                print!("    if n >= {}:\n        hval += {}_ASSO_VALUES[{}]\n", min_len, hash.to_uppercase(), index);
+            }
+        }
+        print!("    return hval\n\n\n");
+
+        if (option[TYPE]) {
+            let names: Vec<String> = self._fields.iter().map(|f| f._name.clone()).collect();
+            print!("# Entries are ({}).\n", names.join(", "));
+        }
+        print!("{} = (\n", wordlist);
+        for keyword in self._entries.iter() {
+            let mut values: Vec<String> = vec![self.string_literal(&keyword._allchars)];
+            values.extend(self.payload(&keyword._rest));
+            if (option[TYPE]) {
+                print!("    ({},),\n", values.join(", "));
+            } else {
+                print!("    {},\n", values[0]);
+            }
+        }
+        print!(")\n\n");
+
+        let offsets: Vec<String> = self._offsets.iter().map(|v| v.to_string()).collect();
+        print!("{}_OFFSETS = (\n{}\n)\n\n\n", wordlist, Language_Output::integer_list(&offsets, "    "));
+
+        let key: &str = if option[TYPE] { "[0]" } else { "" };
+        if (has_char_equivs()) {
+            print!("_FOLD = bytes((\n{}\n))\n\n\n", Language_Output::integer_list(&Language_Output::fold_values(), "    "));
+        }
+        if let Some(name) = compare_function() {
+            print!("# Supplied by the user before the first lookup: {}(keyword, s) returns 0\n# if keyword matches s.\n{} = None\n\n\n", name, name);
+        }
+        print!("def {}(s):\n", lookup);
+        print!("    \"\"\"Return {}\"\"\"\n", if option[TYPE] { "the entry of keyword s, or None if s is not a keyword." } else { "True if s is a keyword." });
+        if let Some(name) = compare_function() {
+            print!("    if {} is None:\n        raise RuntimeError(\"{} is not set\")\n", name, name);
+        }
+        print!("    data = s.encode(\"utf-8\")\n");
+        print!("    if not {} <= len(data) <= {}:\n        return {}\n", self._min_key_len, self._max_key_len,
+               if option[TYPE] { "None" } else { "False" });
+        if (option[SEVENBIT]) {
+            /* The associated values only cover 7-bit characters.  */
+            print!("    if not data.isascii():\n        return {}\n", if option[TYPE] { "None" } else { "False" });
+        }
+        print!("    h = {}(data)\n    if not 0 <= h < len({}_OFFSETS) - 1:\n        return {}\n", hash, wordlist,
+               if option[TYPE] { "None" } else { "False" });
+        print!("    for i in range({}_OFFSETS[h], {}_OFFSETS[h + 1]):\n", wordlist, wordlist);
+        if let Some(name) = compare_function() {
+            print!("        if {}({}[i]{}, s) == 0:\n", name, wordlist, key);
+        } else if (has_char_equivs()) {
+            print!("        if {}[i]{}.encode(\"utf-8\").translate(_FOLD) == data.translate(_FOLD):\n", wordlist, key);
+        } else if (option[UPPERLOWER]) {
+            print!("        if {}[i]{}.encode(\"utf-8\").lower() == data.lower():\n", wordlist, key);
+        } else {
+            print!("        if {}[i]{}.encode(\"utf-8\") == data:\n", wordlist, key);
+        }
+        print!("            return {}\n", if option[TYPE] { format!("{}[i]", wordlist) } else { String::from("True") });
+        print!("    return {}\n", if option[TYPE] { "None" } else { "False" });
+    }
+
+    /* Writes the lookup code in the language selected with -L.  */
+    pub fn output(&self) {
+        if (option[GO]) {
+            self.output_go();
+        } else if (option[JAVA]) {
+            self.output_java();
+        } else if (option[PYTHON]) {
+            self.output_python();
+        }
+    }
+
+}
@@ -0,0 +1,66 @@
+mod input;
+mod keyword;
+mod options;
+
+use std::ptr::null;
+
+/* Emits #line directives, so that compiler errors in the verbatim code
+   and in the struct initializers point at the keyword file rather than
+   at the generated code.  Each directive must start a line of output.  */
+
+/* Emits a directive making the next output line count as line LINENO of
+   FILE_NAME.  Nothing is emitted with --no-lines, or when the input came
+   from standard input.  */
+pub fn output_line_directive(file_name: *const char, lineno: u32) {
+    if (option.get_no_lines() || file_name == null() || lineno == 0) {
+        return;
+    }
+
+    let mut quoted: String = String::new();
+    for c in option_text(file_name).chars() {
+        if (c == '"' || c == '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    print!("#line {} \"{}\"\n", lineno, quoted);
+}
+
+/* Emits a verbatim section, TEXT, with a directive before each piece
+   recorded in SOURCES.  */
+pub fn output_verbatim(text: &[u8], sources: &Vec<Source_Location>) {
+    let mut start: usize = 0;
+    for (i, source) in sources.iter().enumerate() {
+        let end: usize = if i + 1 < sources.len() { sources[i + 1]._offset } else { text.len() };
+        if (start < source._offset) {
+            print!("{}", String::from_utf8_lossy(&text[start..source._offset]));
+        }
+        if (source._offset > 0 && text[source._offset - 1] != b'\n') {
+            print!("\n");
+        }
+        output_line_directive(source._file_name, source._lineno);
+        print!("{}", String::from_utf8_lossy(&text[source._offset..end]));
+        start = end;
+    }
+    if (start < text.len()) {
+        print!("{}", String::from_utf8_lossy(&text[start..]));
+    }
+}
+
+/* Emits the %{ ... %} sections of the input.  */
+pub fn output_verbatim_declarations(input: &Input) {
+    output_verbatim(&input._verbatim_declarations, &input._verbatim_declarations_sources);
+}
+
+/* Emits the code sections after the keywords.  */
+pub fn output_verbatim_code(input: &Input) {
+    output_verbatim(&input._verbatim_code, &input._verbatim_code_sources);
+}
+
+/* Emits a directive pointing at the line of KEYWORD, before its struct
+   initializer in the word list.  */
+pub fn output_keyword_line_directive(keyword: *const Keyword) {
+    if (option[TYPE]) {
+        output_line_directive((*keyword)._file_name, (*keyword)._lineno);
+    }
+}
@@ -0,0 +1,427 @@
+mod input;
+mod keyword;
+mod keyword_list;
+mod options;
+mod positions;
+mod previous;
+
+use std::process;
+use std::ptr::null_mut;
+use std::fs::File;
+use std::io::Write;
+
+/* Version of the model file format.  Bump it whenever the meaning of a
+   member changes.  */
+const MODEL_VERSION: i64 = 1;
+
+/* The options that change the hash function or the lookup, and are thus
+   part of the model.  The language and the other output details are not,
+   so that a model can be turned into code for any language.  */
+static MODEL_OPTIONS: [OptionType; 8] =
+    [OptionType::UPPERLOWER, OptionType::NOLENGTH, OptionType::DUP, OptionType::SEVENBIT,
+     OptionType::TYPE, OptionType::NOTYPE, OptionType::LENTABLE, OptionType::COMP];
+
+/* Header of the binary model format, read by the runtime crate; see
+   runtime/lib.rs for the layout.  */
+const BINARY_MAGIC: &[u8; 4] = b"GPRM";
+const BINARY_VERSION: u16 = 1;
+const BINARY_FLAG_IGNORE_CASE: u16 = 1 << 0;
+const BINARY_FLAG_NO_LENGTH: u16 = 1 << 1;
+
+/* A keyword of the model, in word list order.  */
+struct Model_Keyword {
+    pub _allchars: Vec<u8>,
+
+    /* The struct initializer, with %struct-type.  */
+    pub _rest: Vec<u8>,
+
+    pub _hash_value: i32,
+    pub _final_index: i32
+}
+
+/* The result of the search, written with --emit-model and read back with
+   --from-model: everything the output needs, so that code can be
+   generated again without searching.  */
+struct Model {
+    _option_word: i32,
+
+    /* The key positions, as stored in Positions.  */
+    _positions: Vec<i32>,
+
+    /* The case unification map and the per-position increments; empty if
+       not used.  */
+    _alpha_unify: Vec<u32>,
+    _alpha_inc: Vec<u32>,
+
+    _asso_values: Vec<i32>,
+
+    /* The %char-equiv classes.  */
+    _char_equivs: Vec<Vec<u8>>,
+
+    /* The execution character set the associated values are computed
+       for.  */
+    _target_charset: TargetCharset,
+
+    /* The names of the lookup and hash functions, of the word list and
+       of the keyword field.  */
+    _function_name: String,
+    _hash_name: String,
+    _wordlist_name: String,
+    _slot_name: String,
+
+    /* The struct declaration and the return type of the lookup function,
+       with %struct-type; empty otherwise.  */
+    _struct_decl: String,
+    _return_type: String,
+
+    /* The keywords, duplicates included, in word list order.  */
+    _keywords: Vec<Model_Keyword>
+}
+
+fn join_integers<T: ToString>(values: &[T]) -> String {
+    let mut result: String = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if (i > 0) {
+            result.push_str(if i % 16 == 0 { ",\n    " } else { ", " });
+        }
+        result.push_str(&value.to_string());
+    }
+    return result;
+}
+
+impl Model {
+
+    /* Collects the result of the search.  STRUCT_DECL and RETURN_TYPE are
+       those of the input.  */
+    pub fn new(head: *mut KeywordExt_List, positions: &Positions, alpha_unify: Vec<u32>,
+               alpha_inc: Vec<u32>, asso_values: Vec<i32>, struct_decl: &str, return_type: &str) -> Model {
+
+        let mut keywords: Vec<Model_Keyword> = Vec::new();
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let mut keyword: *mut KeywordExt = (*temp).first();
+            while (keyword != null_mut()) {
+                keywords.push(Model_Keyword {
+                    _allchars: (*keyword).keyword._allchars.clone(),
+                    _rest: (*keyword).keyword._rest.clone(),
+                    _hash_value: (*keyword)._hash_value,
+                    _final_index: (*keyword)._final_index
+                });
+                keyword = (*keyword)._duplicate_link;
+            }
+            temp = (*temp).rest();
+        }
+        keywords.sort_by_key(|k| k._final_index);
+
+        let mut option_word: i32 = 0;
+        for &flag in MODEL_OPTIONS.iter() {
+            if (option[flag]) {
+                option_word |= flag as i32;
+            }
+        }
+
+        Model {
+            _option_word: option_word,
+            _positions: std::slice::from_raw_parts(positions.pointer(), positions.get_size() as usize).to_vec(),
+            _alpha_unify: alpha_unify,
+            _alpha_inc: alpha_inc,
+            _asso_values: asso_values,
+            _char_equivs: option.get_char_equivs().clone(),
+            _target_charset: option.get_target_charset(),
+            _function_name: option_text(option.get_function_name()),
+            _hash_name: option_text(option.get_hash_name()),
+            _wordlist_name: option_text(option.get_wordlist_name()),
+            _slot_name: option_text(option.get_slot_name()),
+            _struct_decl: String::from(struct_decl),
+            _return_type: String::from(return_type),
+            _keywords: keywords
+        }
+    }
+
+    /* Writes the model to FILE_NAME, as JSON.  Keywords, initializers,
+       %char-equiv classes and the texts taken from the input are bytes:
+       each is a string if it is valid UTF-8, and otherwise an array of the
+       byte values (see json_bytes).  */
+    pub fn write(&self, file_name: *const char) {
+        let mut stream: File = match File::create(option_text(file_name)) {
+            Ok(stream) => stream,
+            Err(_) => {
+                eprintln!("{}: cannot create {}\n", program_name, file_name);
+                process::exit(1);
+            }
+        };
+
+        let mut text: String = format!("{{\n  \"version\": {},\n  \"options\": {},\n", MODEL_VERSION, self._option_word);
+        text.push_str(&format!("  \"target_charset\": \"{}\",\n", target_charset_name(self._target_charset)));
+        text.push_str(&format!("  \"positions\": [{}],\n", join_integers(&self._positions)));
+        text.push_str(&format!("  \"alpha_unify\": [{}],\n", join_integers(&self._alpha_unify)));
+        text.push_str(&format!("  \"alpha_inc\": [{}],\n", join_integers(&self._alpha_inc)));
+        text.push_str(&format!("  \"asso_values\": [{}],\n", join_integers(&self._asso_values)));
+        if (!self._char_equivs.is_empty()) {
+            let classes: Vec<String> = self._char_equivs.iter().map(|class| json_bytes(class)).collect();
+            text.push_str(&format!("  \"char_equivs\": [{}],\n", classes.join(", ")));
+        }
+        for (name, value) in [("function_name", &self._function_name), ("hash_name", &self._hash_name),
+                              ("wordlist_name", &self._wordlist_name), ("slot_name", &self._slot_name),
+                              ("struct_decl", &self._struct_decl), ("return_type", &self._return_type)] {
+            text.push_str(&format!("  \"{}\": {},\n", name, json_bytes(value.as_bytes())));
+        }
+        text.push_str("  \"keywords\": [");
+        for (i, keyword) in self._keywords.iter().enumerate() {
+            text.push_str(&format!("{}\n    [{}, {}, {}, {}]", if i == 0 { "" } else { "," },
+                                   json_bytes(&keyword._allchars), keyword._hash_value,
+                                   keyword._final_index, json_bytes(&keyword._rest)));
+        }
+        text.push_str("\n  ]\n}\n");
+
+        if (stream.write_all(text.as_bytes()).is_err()) {
+            eprintln!("{}: error writing {}\n", program_name, file_name);
+            process::exit(1);
+        }
+    }
+
+    /* Writes the model to FILE_NAME in the binary format of the runtime
+       crate.  The payload of each keyword is its struct initializer.  */
+    pub fn write_binary(&self, file_name: *const char) {
+        if (!self._char_equivs.is_empty()) {
+            eprintln!("{}: --model-format=binary does not support %char-equiv\n", program_name);
+            process::exit(1);
+        }
+        if (self._target_charset == TargetCharset::EBCDIC1047) {
+            /* The pool holds the keywords in the input character set.  */
+            eprintln!("{}: --model-format=binary does not support --target-charset=ebcdic-1047\n", program_name);
+            process::exit(1);
+        }
+        let mut data: Vec<u8> = Vec::new();
+
+        let mut flags: u16 = 0;
+        if (self._option_word & OptionType::UPPERLOWER as i32 != 0) {
+            flags |= BINARY_FLAG_IGNORE_CASE;
+        }
+        if (self._option_word & OptionType::NOLENGTH as i32 != 0) {
+            flags |= BINARY_FLAG_NO_LENGTH;
+        }
+        data.extend_from_slice(BINARY_MAGIC);
+        data.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        data.extend_from_slice(&flags.to_le_bytes());
+
+        data.extend_from_slice(&(self._positions.len() as u16).to_le_bytes());
+        for &pos in self._positions.iter() {
+            data.extend_from_slice(&(pos as i16).to_le_bytes());
+        }
+        for values in [&self._alpha_inc, &self._alpha_unify] {
+            data.extend_from_slice(&(values.len() as u16).to_le_bytes());
+            for &value in values.iter() {
+                data.extend_from_slice(&(value as u16).to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&(self._asso_values.len() as u16).to_le_bytes());
+        for &value in self._asso_values.iter() {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        /* The entries, grouped by hash value, and the offset of each
+           group.  */
+        let mut entries: Vec<&Model_Keyword> = self._keywords.iter().collect();
+        entries.sort_by_key(|k| k._hash_value);
+        let table_size: usize = entries.last().map_or(0, |k| k._hash_value as usize + 1);
+        data.extend_from_slice(&(table_size as u32).to_le_bytes());
+        let mut next: usize = 0;
+        for h in 0..table_size + 1 {
+            while (next < entries.len() && (entries[next]._hash_value as usize) < h) {
+                next += 1;
+            }
+            data.extend_from_slice(&(next as u32).to_le_bytes());
+        }
+
+        let mut pool: Vec<u8> = Vec::new();
+        data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for keyword in entries.iter() {
+            for bytes in [&keyword._allchars[..], &keyword._rest[..]] {
+                data.extend_from_slice(&(pool.len() as u32).to_le_bytes());
+                data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                pool.extend_from_slice(bytes);
+            }
+        }
+        data.extend_from_slice(&(pool.len() as u32).to_le_bytes());
+        data.extend_from_slice(&pool);
+
+        let written: bool = match File::create(option_text(file_name)) {
+            Ok(mut stream) => stream.write_all(&data).is_ok(),
+            Err(_) => false
+        };
+        if (!written) {
+            eprintln!("{}: cannot write {}\n", program_name, file_name);
+            process::exit(1);
+        }
+    }
+
+    /* Reads a model written by write().  */
+    pub fn read(file_name: *const char) -> Model {
+        let stream: File = match File::open(option_text(file_name)) {
+            Ok(stream) => stream,
+            Err(_) => {
+                eprintln!("{}: cannot read model {}\n", program_name, file_name);
+                process::exit(1);
+            }
+        };
+
+        let mut reader: Json_Reader = Json_Reader::new(stream, option_text(file_name));
+        let mut result = Model {
+            _option_word: 0,
+            _positions: Vec::new(),
+            _alpha_unify: Vec::new(),
+            _alpha_inc: Vec::new(),
+            _asso_values: Vec::new(),
+            _char_equivs: Vec::new(),
+            _target_charset: TargetCharset::ASCII,
+            _function_name: option_text(option.get_function_name()),
+            _hash_name: option_text(option.get_hash_name()),
+            _wordlist_name: option_text(option.get_wordlist_name()),
+            _slot_name: option_text(option.get_slot_name()),
+            _struct_decl: String::new(),
+            _return_type: String::new(),
+            _keywords: Vec::new()
+        };
+        let mut version: i64 = 0;
+
+        reader.expect(b'{');
+        loop {
+            let name: Vec<u8> = reader.parse_string();
+            reader.expect(b':');
+            match &name[..] {
+                b"version" => version = reader.parse_integer(),
+                b"options" => result._option_word = reader.parse_integer() as i32,
+                b"target_charset" => {
+                    let name: Vec<u8> = reader.parse_string();
+                    result._target_charset = match &name[..] {
+                        b"ascii" => TargetCharset::ASCII,
+                        b"ebcdic-1047" => TargetCharset::EBCDIC1047,
+                        b"latin1" => TargetCharset::LATIN1,
+                        _ => reader.error("unknown target charset")
+                    };
+                }
+                b"positions" => result._positions = reader.parse_integer_array(),
+                b"alpha_unify" => result._alpha_unify = reader.parse_integer_array().iter().map(|&v| v as u32).collect(),
+                b"alpha_inc" => result._alpha_inc = reader.parse_integer_array().iter().map(|&v| v as u32).collect(),
+                b"asso_values" => result._asso_values = reader.parse_integer_array(),
+                b"function_name" => result._function_name = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"hash_name" => result._hash_name = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"wordlist_name" => result._wordlist_name = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"slot_name" => result._slot_name = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"struct_decl" => result._struct_decl = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"return_type" => result._return_type = String::from_utf8_lossy(&reader.parse_bytes()).into_owned(),
+                b"char_equivs" => {
+                    reader.expect(b'[');
+                    if (!reader.accept(b']')) {
+                        loop {
+                            result._char_equivs.push(reader.parse_bytes());
+                            if (reader.accept(b']')) {
+                                break;
+                            }
+                            reader.expect(b',');
+                        }
+                    }
+                }
+                b"keywords" => {
+                    reader.expect(b'[');
+                    if (!reader.accept(b']')) {
+                        loop {
+                            reader.expect(b'[');
+                            let allchars: Vec<u8> = reader.parse_bytes();
+                            reader.expect(b',');
+                            let hash_value: i32 = reader.parse_integer() as i32;
+                            reader.expect(b',');
+                            let final_index: i32 = reader.parse_integer() as i32;
+                            reader.expect(b',');
+                            let rest: Vec<u8> = reader.parse_bytes();
+                            reader.expect(b']');
+                            result._keywords.push(Model_Keyword {
+                                _allchars: allchars,
+                                _rest: rest,
+                                _hash_value: hash_value,
+                                _final_index: final_index
+                            });
+                            if (reader.accept(b']')) {
+                                break;
+                            }
+                            reader.expect(b',');
+                        }
+                    }
+                }
+                _ => reader.error("unknown member")
+            }
+            if (reader.accept(b'}')) {
+                break;
+            }
+            reader.expect(b',');
+        }
+
+        if (version != MODEL_VERSION) {
+            eprintln!("{}: model version {} is not supported, expected {}\n", file_name, version, MODEL_VERSION);
+            process::exit(1);
+        }
+        return result;
+    }
+
+    /* Sets the options and names that the model depends on, overriding the
+       command line.  The language and the other output options are kept.  */
+    pub fn apply_options(&self) {
+        for &flag in MODEL_OPTIONS.iter() {
+            if (self._option_word & flag as i32 != 0) {
+                option.set(flag);
+            } else {
+                option.unset(flag);
+            }
+        }
+        option.select_keyword_set(option_arg(self._function_name.as_bytes()), option_arg(self._wordlist_name.as_bytes()),
+                                  option_arg(self._hash_name.as_bytes()),
+                                  self._option_word & OptionType::TYPE as i32 != 0);
+        option.select_slot_name(option_arg(self._slot_name.as_bytes()));
+        option.set_char_equivs(self._char_equivs.clone());
+        option.set_target_charset_value(self._target_charset);
+    }
+
+    /* Returns the key positions.  */
+    pub fn get_positions(&self) -> Positions {
+        let mut positions: Positions = Positions::default();
+        for &pos in self._positions.iter() {
+            positions.add(pos);
+        }
+        return positions;
+    }
+
+    /* Returns the key positions, as stored in Positions.  */
+    pub fn get_key_positions(&self) -> &Vec<i32> {
+        return &self._positions;
+    }
+
+    pub fn get_alpha_unify(&self) -> &Vec<u32> {
+        return &self._alpha_unify;
+    }
+
+    pub fn get_alpha_inc(&self) -> &Vec<u32> {
+        return &self._alpha_inc;
+    }
+
+    pub fn get_asso_values(&self) -> &Vec<i32> {
+        return &self._asso_values;
+    }
+
+    /* Returns the struct declaration, to be used instead of the input's.  */
+    pub fn get_struct_decl(&self) -> &String {
+        return &self._struct_decl;
+    }
+
+    /* Returns the return type of the lookup function, to be used instead
+       of the input's.  */
+    pub fn get_return_type(&self) -> &String {
+        return &self._return_type;
+    }
+
+    /* Returns the keywords, in word list order.  */
+    pub fn get_keywords(&self) -> &Vec<Model_Keyword> {
+        return &self._keywords;
+    }
+
+}
@@ -1,3 +1,5 @@
+mod check;
+mod keyword_list;
 mod positions;
 use std::ops::Indexmut;
 use std::fs::OpenOptions;
@@ -30,6 +32,15 @@ enum OptionType
   /* Generate C++ code: prototypes, const, class, inline, enum.  */
   CPLUSPLUS = 1 << 5,
 
+  /* Generate a Go package.  */
+  GO = 1 << 28,
+
+  /* Generate a Java class.  */
+  JAVA = 1 << 29,
+
+  /* Generate a Python module.  */
+  PYTHON = 1 << 30,
+
   /* --- Details in the output code --- */
 
   /* Assume 7-bit, not 8-bit, characters.  */
@@ -66,6 +77,19 @@ enum OptionType
      defined elsewhere.  */
   NOTYPE = 1 << 16,
 
+  /* Generate a function mapping struct ids back to keywords.  */
+  REVERSE = 1 << 23,
+
+  /* Also generate a longest-prefix lookup function.  */
+  LONGESTPREFIX = 1 << 24,
+
+  /* Also generate functions returning the canonical keyword index and
+     rewriting the input to the canonical spelling.  */
+  CANONICAL = 1 << 25,
+
+  /* Write the model in the binary format of the runtime crate.  */
+  BINARYMODEL = 1 << 27,
+
   /* --- Algorithm employed by gperf --- */
 
   /* Use the given key positions.  */
@@ -83,12 +107,63 @@ enum OptionType
   /* --- Informative output --- */
 
   /* Enable debugging (prints diagnostics to stderr).  */
-  DEBUG = 1 << 21
+  DEBUG = 1 << 21,
+
+  /* Compile the generated code and check every keyword and near-miss.  */
+  SELFTEST = 1 << 22,
+
+  /* Explain the key positions, associated values and hash values found.  */
+  EXPLAIN = 1 << 26
 }
 
 
 
 
+/* Enumeration of the supported keyword file formats.  */
+
+#[derive(Clone, Copy, PartialEq)]
+enum InputFormat
+{
+  /* gperf's own format: declarations, %%, keywords, %%, code.  */
+  GPERF,
+
+  /* Comma-separated values, with RFC 4180 quoting.  */
+  CSV,
+
+  /* Tab-separated values, without quoting.  */
+  TSV,
+
+  /* A JSON array of objects or arrays, one per keyword.  */
+  JSON
+}
+
+
+/* Enumeration of the supported execution character sets of the
+   generated code.  */
+
+#[derive(Clone, Copy, PartialEq)]
+enum TargetCharset
+{
+  /* ASCII, or any ISO-646 based character set (the default).  */
+  ASCII,
+
+  /* IBM EBCDIC code page 1047, as used on z/OS.  */
+  EBCDIC1047,
+
+  /* ISO-8859-1.  */
+  LATIN1
+}
+
+/* Returns the name of CHARSET, as given to --target-charset.  */
+pub fn target_charset_name(charset: TargetCharset) -> &'static str {
+    return match charset {
+        TargetCharset::ASCII => "ascii",
+        TargetCharset::EBCDIC1047 => "ebcdic-1047",
+        TargetCharset::LATIN1 => "latin1"
+    };
+}
+
+
 /* Class manager for gperf program Options.  */
 struct Options {
 
@@ -101,11 +176,21 @@ struct Options {
     /* Holds the boolean options.  */
     _option_word: i32,
     
-    /* Name of input file.  */
-    _input_file_name: *mut char,
+    /* Names of input files, in command-line order.  */
+    _input_file_names: Vec<*const char>,
     
     /* Name of output file.  */
     _output_file_name: *mut char,
+
+    /* Name of the header file to write, or null.  */
+    _output_header_file_name: *const char,
+
+    /* Compare the output with the output file instead of writing it.  */
+    _check: bool,
+
+    /* Don't emit #line directives pointing back to the input file.  The
+       option word has no bit left for it: the sign bit is kept clear.  */
+    _no_lines: bool,
     
     /* The output language.  */
     _language: *const char,
@@ -149,6 +234,42 @@ struct Options {
     /* Name used for the string pool.  */
     _stringpool_name: *const char,
     
+    /* Format of the input files.  */
+    _input_format: InputFormat,
+
+    /* Character set the generated code runs in.  */
+    _target_charset: TargetCharset,
+
+    /* Struct field holding the id for the reverse table, or null.  */
+    _reverse_field: *const char,
+
+    /* File holding the result of the previous run, or null.  */
+    _previous_file_name: *const char,
+
+    /* Files to write the model to and to read it from, or null.  */
+    _emit_model_file_name: *const char,
+    _from_model_file_name: *const char,
+
+    /* File to write the tests of the lookup function to, or null.  */
+    _emit_tests_file_name: *const char,
+
+    /* Field holding the keyword, for non-gperf input formats.  */
+    _key_field: *const char,
+
+    /* File holding the struct declaration, for non-gperf input formats,
+       or null.  */
+    _struct_declaration_file_name: *const char,
+
+    /* User function comparing a keyword with the input, or null for
+       strcmp/strncmp.  */
+    _compare_function: *const char,
+
+    /* The character equivalence classes given with %char-equiv.  */
+    _char_equivs: Vec<Vec<u8>>,
+
+    /* The names of the keyword sets given with %set.  */
+    _set_names: Vec<String>,
+
     /* Separates keywords from other attributes.  */
     _delimiters: *const char,
     
@@ -219,7 +340,7 @@ struct option {
     val: i32
 }
 
-static long_options: [option;42] =
+static long_options: [option;60] =
 [
   option{ name: "output-file", has_arg: 1, flag: NULL, val: CHAR_MAX + 1 },
   option{ name: "ignore-case", has_arg: 0, flag: NULL, val: CHAR_MAX + 2 },
@@ -262,6 +383,24 @@ static long_options: [option;42] =
   option{ name: "help", has_arg: 0, flag: NULL, val: 'h' as i32},
   option{ name: "version", has_arg: 0, flag: NULL, val: 'v' as i32},
   option{ name: "debug", has_arg: 0, flag: NULL, val: 'd' as i32},
+  option{ name: "self-test", has_arg: 0, flag: NULL, val: CHAR_MAX + 5 },
+  option{ name: "explain", has_arg: 0, flag: NULL, val: CHAR_MAX + 12 },
+  option{ name: "input-format", has_arg: 1, flag: NULL, val: CHAR_MAX + 6 },
+  option{ name: "key-field", has_arg: 1, flag: NULL, val: CHAR_MAX + 7 },
+  option{ name: "struct-declaration", has_arg: 1, flag: NULL, val: CHAR_MAX + 8 },
+  option{ name: "emit-reverse-table", has_arg: 2, flag: NULL, val: CHAR_MAX + 9 },
+  option{ name: "lookup", has_arg: 1, flag: NULL, val: CHAR_MAX + 10 },
+  option{ name: "canonical-index", has_arg: 0, flag: NULL, val: CHAR_MAX + 11 },
+  option{ name: "previous", has_arg: 1, flag: NULL, val: CHAR_MAX + 13 },
+  option{ name: "emit-model", has_arg: 1, flag: NULL, val: CHAR_MAX + 14 },
+  option{ name: "from-model", has_arg: 1, flag: NULL, val: CHAR_MAX + 15 },
+  option{ name: "model-format", has_arg: 1, flag: NULL, val: CHAR_MAX + 16 },
+  option{ name: "output-header", has_arg: 1, flag: NULL, val: CHAR_MAX + 17 },
+  option{ name: "no-lines", has_arg: 0, flag: NULL, val: CHAR_MAX + 18 },
+  option{ name: "check", has_arg: 0, flag: NULL, val: CHAR_MAX + 19 },
+  option{ name: "compare-function", has_arg: 1, flag: NULL, val: CHAR_MAX + 20 },
+  option{ name: "target-charset", has_arg: 1, flag: NULL, val: CHAR_MAX + 21 },
+  option{ name: "emit-tests", has_arg: 1, flag: NULL, val: CHAR_MAX + 22 },
   option{ name: NULL, has_arg: 0, flag: NULL, val: 0 }
 ];
 
@@ -275,9 +414,24 @@ impl Options {
     /* Constructor.  */
     pub fn new(&mut self) -> Options {
         _option_word = C;
-        _input_file_name = null_mut();
+        _input_file_names = Vec::new();
         _output_file_name = null_mut();
+        _output_header_file_name = null_mut();
+        _check = false;
+        _no_lines = false;
         _language = null_mut();
+        _input_format = InputFormat::GPERF;
+        _target_charset = TargetCharset::ASCII;
+        _key_field = null_mut();
+        _struct_declaration_file_name = null_mut();
+        _compare_function = null_mut();
+        _char_equivs = Vec::new();
+        _set_names = Vec::new();
+        _reverse_field = null_mut();
+        _previous_file_name = null_mut();
+        _emit_model_file_name = null_mut();
+        _from_model_file_name = null_mut();
+        _emit_tests_file_name = null_mut();
         _jump = DEFAULT_JUMP_VALUE;
         _initial_asso_value = 0;
         _asso_iterations = 0;
@@ -514,6 +668,30 @@ impl Options {
                     from_u32(129) => _option_word |= OptionType::UPPERLOWER as i32, /* Case insignificant.  */
                     from_u32(130) => _option_word |= NULLSTRINGS, /* Use NULL instead of "".  */
                     from_u32(131) => _lengthtable_name = /*getopt*/optarg, /* Sets the name for the length table array.  */
+                    from_u32(132) => _option_word |= OptionType::SELFTEST as i32, /* Verify the generated lookup.  */
+                    from_u32(133) => set_input_format (/*getopt*/optarg), /* Set the keyword file format.  */
+                    from_u32(134) => _key_field = /*getopt*/optarg, /* Set the field holding the keyword.  */
+                    from_u32(135) => { /* Read the struct declaration from a file; implies --struct-type.  */
+                        _struct_declaration_file_name = /*getopt*/optarg;
+                        _option_word |= OptionType::TYPE as i32;
+                    }
+                    from_u32(136) => { /* Generate the id to keyword mapping.  */
+                        _option_word |= OptionType::REVERSE as i32;
+                        _reverse_field = /*getopt*/optarg;
+                    }
+                    from_u32(137) => set_lookup (/*getopt*/optarg), /* Set the kind of lookup function.  */
+                    from_u32(138) => _option_word |= OptionType::CANONICAL as i32 | OptionType::UPPERLOWER as i32, /* Canonical index, implies case insignificance.  */
+                    from_u32(139) => _option_word |= OptionType::EXPLAIN as i32, /* Explain the hash function found.  */
+                    from_u32(140) => _previous_file_name = /*getopt*/optarg, /* Reuse and save the search result.  */
+                    from_u32(141) => _emit_model_file_name = /*getopt*/optarg, /* Save the model.  */
+                    from_u32(142) => _from_model_file_name = /*getopt*/optarg, /* Generate code from a saved model.  */
+                    from_u32(143) => set_model_format (/*getopt*/optarg), /* Set the format of --emit-model.  */
+                    from_u32(144) => _output_header_file_name = /*getopt*/optarg, /* Set the header file name.  */
+                    from_u32(145) => _no_lines = true, /* Don't emit #line directives.  */
+                    from_u32(146) => _check = true, /* Compare with the output file.  */
+                    from_u32(147) => _compare_function = /*getopt*/optarg, /* Set the comparison function.  */
+                    from_u32(148) => set_target_charset (/*getopt*/optarg), /* Set the execution character set.  */
+                    from_u32(149) => _emit_tests_file_name = /*getopt*/optarg, /* Write tests of the lookup function.  */
                     _ => {
                         short_usage(std::io::stderr());
                         std::process::exit(1);
@@ -521,89 +699,193 @@ impl Options {
                     
                 }
 
-                if (/*getopt*/optind < argc){
-                    _input_file_name = argv[/*getopt*/optind];
-                    optind += 1;
-                }
-    
-                if (/*getopt*/optind < argc) {
-                    eprint!("Extra trailing arguments to {}.\n", program_name);
-                    short_usage(std::io::stderr());
-                    std::process::exit(1);
-                }
-
             }
+
+        /* All remaining arguments are input files.  Their keyword lists
+           are concatenated.  */
+        while (/*getopt*/optind < argc) {
+            _input_file_names.push(argv[/*getopt*/optind]);
+            optind += 1;
+        }
+
+        if (_output_header_file_name != null_mut()
+            && _option_word & (OptionType::GO as i32 | OptionType::JAVA as i32 | OptionType::PYTHON as i32) != 0) {
+            eprint!("{}: --output-header is only supported for C and C++\n", program_name);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+
+        if (_target_charset != TargetCharset::ASCII
+            && _option_word & (OptionType::GO as i32 | OptionType::JAVA as i32 | OptionType::PYTHON as i32) != 0) {
+            eprint!("{}: --target-charset is only supported for C and C++\n", program_name);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+
+        if (_check && (_output_file_name == null_mut() || option_text(_output_file_name) == "-")) {
+            eprint!("{}: --check needs --output-file\n", program_name);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+
+        if (_emit_tests_file_name != null_mut() && (_output_file_name == null_mut() || option_text(_output_file_name) == "-")) {
+            eprint!("{}: --emit-tests needs --output-file\n", program_name);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+
+        if (_struct_declaration_file_name != null_mut() && _input_format == InputFormat::GPERF) {
+            eprint!("{}: --struct-declaration is only for --input-format=csv, tsv or json\n", program_name);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
     }
 
-    /* Prints the given options.  */
-    pub const fn print_options(&mut self) {
-        print!("/* Command-line: ");
+    /* Prints the banner of the output: the tool version, the command line,
+       the input files and their digest, and the options in effect after
+       the % directives of the input files.  LISTS are the keyword lists
+       of all sets, for the digest.  */
+    pub fn print_options(&mut self, lists: &Vec<*mut Keyword_List>) {
+        print!("{}", self.comment_line(&format!("{} code produced by gperf version {}", self.language_name(), version_string)));
 
+        let mut args: Vec<String> = Vec::new();
         let mut i: i32 = 0;
         while (i < _argument_count) {
+            args.push(shell_quote_argument(&option_text(*(_argument_vector.offset(i)))));
+            i += 1;
+        }
+        print!("{}", self.comment_line(&format!("Command-line: {}", args.join(" "))));
 
-            let mut arg: *const char = *(_argument_vector.offset(i));
-
-            /* Escape arg if it contains shell metacharacters.  */
-            if (*arg == '-') {
+        let inputs: Vec<String> = _input_file_names.iter().map(|&name| shell_quote_argument(&option_text(name))).collect();
+        print!("{}", self.comment_line(&format!("Input: {}", if inputs.is_empty() { String::from("standard input") } else { inputs.join(" ") })));
+        output_digest(lists);
 
-                print!("{}", *arg);
-                arg = arg.offset(1);
+        let effective: Vec<String> = self.effective_arguments().iter()
+            .map(|arg| if arg.starts_with('%') { arg.clone() } else { shell_quote_argument(arg) })
+            .collect();
+        print!("{}", self.comment_line(&format!("Effective options: {}", effective.join(" "))));
+    }
 
-                if(*arg >= 'A' && *arg <= 'Z' || *arg >= 'a' && *arg <= 'z') {
-                    print!("{}", *arg);
-                    arg = arg.offset(1);
-                } else if(*arg == '-') {
-                    loop {
-                        print!("{}", *arg);
-                        arg = arg.offset(1);
+    /* Returns TEXT as a comment of the output language, with its newline.
+       Python has no block comments, so each line of TEXT gets a "#".  */
+    pub fn comment_line(&mut self, text: &str) -> String {
+        if (_option_word & OptionType::PYTHON as i32 != 0) {
+            return text.split('\n').map(|line| format!("# {}\n", line)).collect();
+        }
+        return format!("/* {} */\n", text);
+    }
 
-                        if(*arg >= 'A' && *arg <= 'Z' || *arg >= 'a' && *arg <= 'z' || *arg == '-') {
-                            break;
-                        }
-                    }
+    /* Returns the name of the output language, as given to --language.  */
+    fn language_name(&mut self) -> &'static str {
+        if (_option_word & OptionType::KRC as i32 != 0) {
+            return "KR-C";
+        } else if (_option_word & OptionType::ANSIC as i32 != 0) {
+            return "ANSI-C";
+        } else if (_option_word & OptionType::CPLUSPLUS as i32 != 0) {
+            return "C++";
+        } else if (_option_word & OptionType::GO as i32 != 0) {
+            return "Go";
+        } else if (_option_word & OptionType::JAVA as i32 != 0) {
+            return "Java";
+        } else if (_option_word & OptionType::PYTHON as i32 != 0) {
+            return "Python";
+        }
+        return "C";
+    }
 
-                    if(*arg == '=') {
-                        print!("{}", *arg);
-                        arg = arg.offset(1);
-                    }
-                } 
+    /* Returns the options in effect, as command-line arguments: those
+       given on the command line and those set by % directives, but not
+       the defaults.  The %char-equiv and %set directives, which have no
+       command-line form, follow as directives.  */
+    pub fn effective_arguments(&mut self) -> Vec<String> {
+        let mut result: Vec<String> = vec![format!("--language={}", self.language_name())];
+
+        let flags: [(OptionType, &str); 16] = [
+            (OptionType::TYPE, "--struct-type"),
+            (OptionType::NOTYPE, "--omit-struct-type"),
+            (OptionType::UPPERLOWER, "--ignore-case"),
+            (OptionType::SEVENBIT, "--seven-bit"),
+            (OptionType::LENTABLE, "--compare-lengths"),
+            (OptionType::COMP, "--compare-strncmp"),
+            (OptionType::CONST, "--readonly-tables"),
+            (OptionType::ENUM, "--enum"),
+            (OptionType::INCLUDE, "--includes"),
+            (OptionType::GLOBAL, "--global-table"),
+            (OptionType::NULLSTRINGS, "--null-strings"),
+            (OptionType::SHAREDLIB, "--pic"),
+            (OptionType::DUP, "--duplicates"),
+            (OptionType::NOLENGTH, "--no-strlen"),
+            (OptionType::RANDOM, "--random"),
+            (OptionType::CANONICAL, "--canonical-index")
+        ];
+        for &(flag, name) in flags.iter() {
+            if (_option_word & flag as i32 != 0) {
+                result.push(String::from(name));
             }
-
-            if(strpbrk (arg, "\t\n !\"#$&'()*;<>?[\\]`{|}~") != null_mut()) {
-                if(strchr(arg, '\'') != null_mut()) {
-                    print!("\"");
-                    while (*arg != '\0') {
-                        if (*arg == '\"' || *arg == '\\' || *arg == '$' || *arg == '`') {
-                            print!("\\");
-                        }
-                        print!("\"");
-                        arg = arg.offset(1);
-                    }
-
-                    print!("\"");
-                } else {
-                    print!("\'");
-                    while (*arg) {
-                        if (*arg == '\\') {
-                            print!("\\");
-                        }
-                        print!("{}", *arg);
-                        arg = arg.offset(1);
-                    }
-                    print!("\'");
-                }
- 
+        }
+        if (_no_lines) {
+            result.push(String::from("--no-lines"));
+        }
+        if (_option_word & OptionType::LONGESTPREFIX as i32 != 0) {
+            result.push(String::from("--lookup=longest-prefix"));
+        }
+        if (_option_word & OptionType::REVERSE as i32 != 0) {
+            result.push(if _reverse_field == null_mut() {
+                String::from("--emit-reverse-table")
             } else {
-                print!("{}", arg);
-            }
+                format!("--emit-reverse-table={}", option_text(_reverse_field))
+            });
+        }
+        if (_option_word & OptionType::SWITCH as i32 != 0) {
+            result.push(format!("--switch={}", _total_switches));
+        }
+        if (_compare_function != null_mut()) {
+            result.push(format!("--compare-function={}", option_text(_compare_function)));
+        }
+        if (_target_charset != TargetCharset::ASCII) {
+            result.push(format!("--target-charset={}", target_charset_name(_target_charset)));
+        }
+        if (_option_word & OptionType::POSITIONS as i32 != 0) {
+            result.push(format!("--key-positions={}", _key_positions.to_string()));
+        }
 
-            print!(" ");
+        let names: [(*const char, *const char, &str); 9] = [
+            (_function_name, DEFAULT_FUNCTION_NAME, "--lookup-function-name"),
+            (_hash_name, DEFAULT_HASH_NAME, "--hash-function-name"),
+            (_wordlist_name, DEFAULT_WORDLIST_NAME, "--word-array-name"),
+            (_lengthtable_name, DEFAULT_LENGTHTABLE_NAME, "--length-table-name"),
+            (_stringpool_name, DEFAULT_STRINGPOOL_NAME, "--string-pool-name"),
+            (_slot_name, DEFAULT_SLOT_NAME, "--slot-name"),
+            (_initializer_suffix, DEFAULT_INITIALIZER_SUFFIX, "--initializer-suffix"),
+            (_class_name, DEFAULT_CLASS_NAME, "--class-name"),
+            (_delimiters, DEFAULT_DELIMITERS, "--delimiters")
+        ];
+        for &(value, default_value, name) in names.iter() {
+            if (option_text(value) != option_text(default_value)) {
+                result.push(format!("{}={}", name, option_text(value)));
+            }
+        }
 
-            i += 1;
+        if (_jump != DEFAULT_JUMP_VALUE) {
+            result.push(format!("--jump={}", _jump));
+        }
+        if (_initial_asso_value != 0) {
+            result.push(format!("--initial-asso={}", _initial_asso_value));
+        }
+        if (_asso_iterations != 0) {
+            result.push(format!("--multiple-iterations={}", _asso_iterations));
+        }
+        if (_size_multiple != 1.0) {
+            result.push(format!("--size-multiple={}", _size_multiple));
         }
 
-        print!(" */");
+        for class in _char_equivs.iter() {
+            result.push(format!("%char-equiv {}", directive_string(class)));
+        }
+        for name in _set_names.iter() {
+            result.push(format!("%set {}", name));
+        }
+        return result;
     }
 
     /* Accessors.  */
@@ -615,10 +897,28 @@ impl Options {
         _option_word |= option as i32;
     }
 
-    /* Returns the input file name.  */
+    /* Clears a given boolean option.  */
+    #[inline]
+    pub fn unset(&mut self, mut option: OptionType) {
+        _option_word &= !(option as i32);
+    }
+
+    /* Returns all boolean options.  */
+    #[inline]
+    pub const fn get_option_word(&mut self) -> i32 {
+        return _option_word;
+    }
+
+    /* Returns the first input file name, or null for standard input.  */
     #[inline]
     pub const fn get_input_file_name(&mut self) -> *const char {
-        return _input_file_name;
+        return if _input_file_names.is_empty() { null() } else { _input_file_names[0] };
+    }
+
+    /* Returns all input file names.  */
+    #[inline]
+    pub const fn get_input_file_names(&mut self) -> &Vec<*const char> {
+        return &_input_file_names;
     }
 
     /* Returns the output file name.  */
@@ -627,12 +927,31 @@ impl Options {
         return output_file_name;
     }
 
+    /* Returns the header file name, or null.  */
+    #[inline]
+    pub const fn get_output_header_file_name(&mut self) -> *const char {
+        return _output_header_file_name;
+    }
+
+    /* Returns whether --no-lines was given.  */
+    #[inline]
+    pub const fn get_no_lines(&mut self) -> bool {
+        return _no_lines;
+    }
+
+    /* Returns whether --check was given.  */
+    #[inline]
+    pub const fn get_check(&mut self) -> bool {
+        return _check;
+    }
+
     /* Sets the output language, if not already set.  */
     pub fn set_language(&mut self, mut language: *const char) {
         if(_language == null_mut()) {
             _language = language;
             _option_word &= !(OptionType::KRC as i32 |OptionType::C as i32 | OptionType::ANSIC as i32 
-                    | OptionType::CPLUSPLUS as i32);
+                    | OptionType::CPLUSPLUS as i32 | OptionType::GO as i32 | OptionType::JAVA as i32
+                    | OptionType::PYTHON as i32);
             if (!strcmp (language, "KR-C")) {
                 _option_word |= OptionType::KRC as i32;
             }
@@ -649,6 +968,18 @@ impl Options {
             else if (!strcmp (language, "C++")) {
                 _option_word |= OptionType::CPLUSPLUS as i32;
             }
+
+            else if (!strcmp (language, "Go")) {
+                _option_word |= OptionType::GO as i32;
+            }
+
+            else if (!strcmp (language, "Java")) {
+                _option_word |= OptionType::JAVA as i32;
+            }
+
+            else if (!strcmp (language, "Python")) {
+                _option_word |= OptionType::PYTHON as i32;
+            }
                 
             else {
                 eprintln!("unsupported language option {}, defaulting to C\n",
@@ -658,6 +989,140 @@ impl Options {
         }
     }
 
+    /* Returns the input file format.  */
+    #[inline]
+    pub const fn get_input_format(&mut self) -> InputFormat {
+        return _input_format;
+    }
+
+    /* Sets the input file format.  */
+    pub fn set_input_format(&mut self, mut format: *const char) {
+        if (!strcmp (format, "gperf")) {
+            _input_format = InputFormat::GPERF;
+        } else if (!strcmp (format, "csv")) {
+            _input_format = InputFormat::CSV;
+        } else if (!strcmp (format, "tsv")) {
+            _input_format = InputFormat::TSV;
+        } else if (!strcmp (format, "json")) {
+            _input_format = InputFormat::JSON;
+        } else {
+            eprint!("unsupported input format {}, use gperf, csv, tsv or json\n", format);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+    }
+
+    /* Returns the execution character set of the generated code.  */
+    #[inline]
+    pub const fn get_target_charset(&mut self) -> TargetCharset {
+        return _target_charset;
+    }
+
+    /* Sets the execution character set of the generated code, from a
+       model file.  */
+    pub fn set_target_charset_value(&mut self, mut charset: TargetCharset) {
+        _target_charset = charset;
+    }
+
+    /* Sets the execution character set of the generated code.  */
+    pub fn set_target_charset(&mut self, mut charset: *const char) {
+        if (!strcmp (charset, "ascii")) {
+            _target_charset = TargetCharset::ASCII;
+        } else if (!strcmp (charset, "ebcdic-1047")) {
+            _target_charset = TargetCharset::EBCDIC1047;
+        } else if (!strcmp (charset, "latin1")) {
+            _target_charset = TargetCharset::LATIN1;
+        } else {
+            eprint!("unsupported target charset {}, use ascii, ebcdic-1047 or latin1\n", charset);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+    }
+
+    /* Returns the field holding the keyword, or null for the default.  */
+    #[inline]
+    pub const fn get_key_field(&mut self) -> *const char {
+        return _key_field;
+    }
+
+    /* Returns the file holding the struct declaration, or null.  */
+    #[inline]
+    pub const fn get_struct_declaration_file_name(&mut self) -> *const char {
+        return _struct_declaration_file_name;
+    }
+
+    /* Returns the struct field holding the id for the reverse table,
+       or null for the first integer field.  */
+    #[inline]
+    pub const fn get_reverse_field(&mut self) -> *const char {
+        return _reverse_field;
+    }
+
+    /* Returns the file holding the result of the previous run, or null.  */
+    #[inline]
+    pub const fn get_previous_file_name(&mut self) -> *const char {
+        return _previous_file_name;
+    }
+
+    /* Returns the file to write the model to, or null.  */
+    #[inline]
+    pub const fn get_emit_model_file_name(&mut self) -> *const char {
+        return _emit_model_file_name;
+    }
+
+    /* Returns the file to write the tests of the lookup function to, or null.  */
+    #[inline]
+    pub const fn get_emit_tests_file_name(&mut self) -> *const char {
+        return _emit_tests_file_name;
+    }
+
+    /* Returns the file to read the model from instead of searching, or null.  */
+    #[inline]
+    pub const fn get_from_model_file_name(&mut self) -> *const char {
+        return _from_model_file_name;
+    }
+
+    /* Sets the format of the file written by --emit-model: json or binary.  */
+    pub fn set_model_format(&mut self, mut format: *const char) {
+        if (!strcmp (format, "json")) {
+            _option_word &= !(OptionType::BINARYMODEL as i32);
+        } else if (!strcmp (format, "binary")) {
+            _option_word |= OptionType::BINARYMODEL as i32;
+        } else {
+            eprint!("unsupported model format {}, use json or binary\n", format);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+    }
+
+    /* Returns the name of the user's comparison function, or null.  */
+    #[inline]
+    pub const fn get_compare_function(&mut self) -> *const char {
+        return _compare_function;
+    }
+
+    /* Sets the comparison function, if not already set.  */
+    pub fn set_compare_function(&mut self, mut name: *const char) {
+        if (_compare_function == null_mut()) {
+            _compare_function = name;
+        }
+    }
+
+    /* Sets the kind of lookup function: exact or longest-prefix.  */
+    pub fn set_lookup(&mut self, mut lookup: *const char) {
+        if (!strcmp (lookup, "exact")) {
+            _option_word &= !(OptionType::LONGESTPREFIX as i32);
+        } else if (!strcmp (lookup, "longest-prefix")) {
+            /* Candidate prefixes are not NUL-terminated where the prefix
+               ends, so the comparison must use the length table.  */
+            _option_word |= OptionType::LONGESTPREFIX as i32 | OptionType::LENTABLE as i32;
+        } else {
+            eprint!("unsupported lookup {}, use exact or longest-prefix\n", lookup);
+            short_usage(std::io::stderr());
+            std::process::exit(1);
+        }
+    }
+
     /* Returns the jump value.  */
     #[inline]
     pub const fn get_jump(&mut self) -> i32 {
@@ -727,6 +1192,11 @@ impl Options {
         
     }
 
+    /* Sets the keyword key name, overriding one already given.  */
+    pub fn select_slot_name(&mut self, mut name: *const char) {
+        _slot_name = name;
+    }
+
     /* Returns the struct initializer suffix.  */
     #[inline]
     pub const fn get_initializer_suffix(&mut self) -> *const char {
@@ -786,6 +1256,21 @@ impl Options {
     
     }
 
+    /* Switches to the names and struct type of a keyword set, or back to
+       the global ones.  Unlike the setters above, this overrides names
+       that were already given.  */
+    pub fn select_keyword_set(&mut self, mut function_name: *const char, mut wordlist_name: *const char,
+                              mut hash_name: *const char, with_type: bool) {
+        _function_name = function_name;
+        _wordlist_name = wordlist_name;
+        _hash_name = hash_name;
+        if (with_type) {
+            self.set(OptionType::TYPE);
+        } else {
+            self.unset(OptionType::TYPE);
+        }
+    }
+
     /* Returns the length table array name.  */
     pub const fn get_lengthable_name(&mut self) -> *const char {
         return _lengthable_name;
@@ -820,6 +1305,27 @@ impl Options {
         return _delimiters;
     }
 
+    /* Returns the character equivalence classes.  */
+    #[inline]
+    pub const fn get_char_equivs(&mut self) -> &Vec<Vec<u8>> {
+        return &_char_equivs;
+    }
+
+    /* Adds a class of characters to be treated as equivalent.  */
+    pub fn add_char_equiv(&mut self, mut chars: Vec<u8>) {
+        _char_equivs.push(chars);
+    }
+
+    /* Replaces the character equivalence classes.  */
+    pub fn set_char_equivs(&mut self, mut char_equivs: Vec<Vec<u8>>) {
+        _char_equivs = char_equivs;
+    }
+
+    /* Records the name of a keyword set.  */
+    pub fn add_set_name(&mut self, mut name: String) {
+        _set_names.push(name);
+    }
+
     /* Sets the delimiters string, if not already set.  */
     pub fn set_delimiters(&mut self, mut delimiters: *const char) {
 
@@ -848,21 +1354,30 @@ impl Options {
 
         writeln!(stream, "GNU 'gperf' generates perfect hash functions.");
         writeln!(stream, "");
-        writeln!(stream, "Usage: {} [OPTION]... [INPUT-FILE]", program_name);
+        writeln!(stream, "Usage: {} [OPTION]... [INPUT-FILE]...", program_name);
         writeln!(stream, "");
         writeln!(stream, "If a long option shows an argument as mandatory, then it is mandatory\n for the equivalent short option also.");
         writeln!(stream, "");
+        writeln!(stream, "Several input files may be given; their keywords are concatenated.");
+        writeln!(stream, "");
         writeln!(stream, "Output file location:");
         writeln!(stream, "      --output-file=FILE Write output to specified file.");
+        writeln!(stream, "      --output-header=FILE\n                         Write the struct declaration, the constants and the\n                         function prototypes to FILE, and include it from the\n                         output file. Only for C and C++.");
+        writeln!(stream, "      --check            Generate the output in memory and compare it with\n                         the output file instead of writing it. Exits with\n                         a diff if the output file is stale.");
+        writeln!(stream, "      --emit-tests=FILE  Also write to FILE a test program in the output\n                         language, checking that every keyword is found with\n                         its struct fields and that near-miss strings are\n                         rejected. The C test is linked with the generated\n                         code. Needs --output-file.");
         writeln!(stream, "The results are written to standard output if no output file is specified\n or if it is -.");
         writeln!(stream, "");
         writeln!(stream, "Input file interpretation:");
         writeln!(stream, "  -e, --delimiters=DELIMITER-LIST\n                         Allow user to provide a string containing delimiters\n                         used to separate keywords from their attributes.\n                         Default is \",\".\"");
         writeln!(stream, "  -t, --struct-type      Allows the user to include a structured type\n                         declaration for generated code. Any text before %%%%\n                         is considered part of the type declaration. Key\n                         words and additional fields may follow this, one\n                         group of fields per line.");    
+        writeln!(stream, "      --input-format=FORMAT\n                         Format of the input files: gperf (the default), csv,\n                         tsv or json. The keyword is taken from one field and\n                         the remaining fields become the other attributes.");
+        writeln!(stream, "      --key-field=FIELD  Field holding the keyword: a 1-based column number,\n                         or a member name for JSON objects. Default is the\n                         first column, or the slot name for JSON objects.");
+        writeln!(stream, "      --struct-declaration=FILE\n                         Read the struct declaration for --struct-type from\n                         FILE, for the csv, tsv and json formats. The other\n                         fields initialize the struct fields, JSON object\n                         members by name. Implies --struct-type.");
         writeln!(stream, "      --ignore-case      Consider upper and lower case ASCII characters as\n                         equivalent. Note that locale dependent case mappings\n                         are ignored.");
         writeln!(stream, "");
         writeln!(stream, "Language for the output code:");
-        writeln!(stream, "  -L, --language=LANGUAGE-NAME\n                         Generates code in the specified language. Languages\n                         handled are currently C++, ANSI-C, C, and KR-C. The                         default is C.");
+        writeln!(stream, "  -L, --language=LANGUAGE-NAME\n                         Generates code in the specified language. Languages\n                         handled are currently C++, ANSI-C, C, KR-C, Go, Java\n                         and Python. The                         default is C.");
+        writeln!(stream, "      --target-charset=CHARSET\n                         Character set of the machine running the generated\n                         code: ascii (the default), ebcdic-1047 or latin1.\n                         The associated values are computed for its byte\n                         values. Only for C and C++.");
         writeln!(stream, "");
         writeln!(stream, "Details in the output code:");
         writeln!("  -K, --slot-name=NAME   Select name of the keyword component in the keyword\n                         structure.");
@@ -873,6 +1388,7 @@ impl Options {
         writeln!(stream, "  -7, --seven-bit        Assume 7-bit characters.\n");
         writeln!(stream, "  -l, --compare-lengths  Compare key lengths before trying a string\n                         comparison. This is necessary if the keywords\n                         contain NUL bytes. It also helps cut down on the\n                         number of string comparisons made during the lookup.\n");
         writeln!(stream, "  -c, --compare-strncmp  Generate comparison code using strncmp rather than\n                         strcmp.");
+        writeln!(stream, "      --compare-function=NAME\n                         Compare the input with the candidate keyword by\n                         calling the user-supplied function NAME instead of\n                         strcmp. The hash is still computed on the raw bytes.");
         writeln!(stream, "  -C, --readonly-tables  Make the contents of generated lookup tables\n                         constant, i.e., readonly.");
         writeln!(stream, "  -E, --enum             Define constant values using an enum local to the\n                         lookup function rather than with defines.");
         writeln!(stream, "  -I, --includes         Include the necessary system include file <string.h>\n                         at the beginning of the code.");
@@ -883,6 +1399,10 @@ impl Options {
         writeln!(stream, "  -W, --word-array-name=NAME\n                         Specify name of word list array. Default name is\n                         'wordlist'.\n");
         writeln!(stream, "  -S, --switch=COUNT     Causes the generated C code to use a switch\n                         statement scheme, rather than an array lookup table.\n                         This can lead to a reduction in both time and space\n                         requirements for some keyfiles. The COUNT argument\n                         determines how many switch statements are generated.\n                         A value of 1 generates 1 switch containing all the\n                         elements, a value of 2 generates 2 tables with 1/2\n                         the elements in each table, etc. If COUNT is very\n                         large, say 1000000, the generated C code does a\n                         binary search.\n");
         writeln!(stream, "  -T, --omit-struct-type\n                         Prevents the transfer of the type declaration to the\n                         output file. Use this option if the type is already\n                         defined elsewhere.\n");
+        writeln!(stream, "      --emit-reverse-table[=FIELD]\n                         Also generate a function mapping the integer id in\n                         struct field FIELD (default: the first integer field)\n                         back to the keyword. Duplicate ids are an error.");
+        writeln!(stream, "      --lookup=KIND      Kind of lookup function: exact (the default) or\n                         longest-prefix, which also generates a function\n                         returning the longest keyword that is a prefix of the\n                         input, and its length. Implies --compare-lengths.");
+        writeln!(stream, "      --canonical-index  Also generate functions returning the index of the\n                         matched keyword in input order, and rewriting the\n                         input to the keyword's canonical spelling, that of\n                         its first occurrence. Implies --ignore-case.");
+        writeln!(stream, "      --no-lines         Don't emit #line directives pointing back to the\n                         input file before the verbatim code and the struct\n                         initializers.");
         writeln!(stream, "");
         writeln!(stream, "Algorithm employed by gperf:");
         writeln!(stream, "  -k, --key-positions=KEYS\n                         Select the key positions used in the hash function.\n                         The allowable choices range between 1-{}, inclusive.\n                         The positions are separated by commas, ranges may be\n                         used, and key positions may occur in any order.\n                         Also, the meta-character '*' causes the generated\n                         hash function to consider ALL key positions, and $\n                         indicates the \"final character\" of a key, e.g.,\n                         $,1,2,4,6-10.\n", Positions::MAX_KEY_POS);
@@ -893,8 +1413,10 @@ impl Options {
         writeln!(stream, "  -n, --no-strlen        Do not include the length of the keyword when\n                         computing the hash function.");
         writeln!(stream, "  -r, --random           Utilizes randomness to initialize the associated\n                         values table.");
         writeln!(stream, "  -s, --size-multiple=N  Affects the size of the generated hash table. The\n                         numeric argument N indicates \"how many times larger\n                         or smaller\" the associated value range should be,\n                         in relationship to the number of keys, e.g. a value\n                         of 3 means \"allow the maximum associated value to\n                         be about 3 times larger than the number of input\n                         keys\". Conversely, a value of 1/3 means \"make the\n                         maximum associated value about 3 times smaller than\n                         the number of input keys\". A larger table should\n                         decrease the time required for an unsuccessful\n                         search, at the expense of extra table space. Default\n                         value is 1.");
+        writeln!(stream, "      --previous=FILE    Start from the key positions and associated values\n                         saved in FILE by an earlier run, keeping the hash\n                         values of unchanged keywords, and save the new ones\n                         there. Falls back to a full search if the keyword\n                         set cannot be extended this way.");
+        writeln!(stream, "      --emit-model=FILE  Write the key positions, associated values, word\n                         list order and the options, names and struct\n                         declaration they depend on to FILE.\n      --from-model=FILE  Generate code from a model written by --emit-model\n                         instead of searching. The output language and other\n                         output options may differ from the original run.\n      --model-format=FORMAT\n                         Format of the --emit-model file: json (the default)\n                         or binary, which the runtime crate loads.");
         writeln!(stream, "");
-        writeln!(stream, "Informative output:\n  -h, --help             Print this message.\n  -v, --version          Print the gperf version number.\n  -d, --debug            Enables the debugging option (produces verbose\n                         output to the standard error)." );
+        writeln!(stream, "Informative output:\n  -h, --help             Print this message.\n  -v, --version          Print the gperf version number.\n  -d, --debug            Enables the debugging option (produces verbose\n                         output to the standard error).\n      --self-test        Compile the generated code with the host compiler and\n                         check that every keyword is found and that near-miss\n                         strings are rejected.\n      --explain          Explain on the standard error why the key positions\n                         were chosen, and how the associated values, the hash\n                         values and the search parameters relate." );
         writeln!(stream, "");
         writeln!(stream, "Report bugs to <bug-gnu-gperf@gnu.org>.");
     }
@@ -920,6 +1442,9 @@ impl Drop for Options {
 \nC is...........: {}
 \nANSIC is.......: {}
 \nCPLUSPLUS is...: {}
+\nGO is..........: {}
+\nJAVA is........: {}
+\nPYTHON is......: {}
 \nSEVENBIT is....: {}
 \nLENTABLE is....: {}
 \nCOMP is........: {}
@@ -935,6 +1460,13 @@ impl Drop for Options {
 \nNOLENGTH is....: {}
 \nRANDOM is......: {}
 \nDEBUG is.......: {}
+\nSELFTEST is....: {}
+\nREVERSE is.....: {}
+\nLONGESTPREFIX is: {}
+\nCANONICAL is...: {}
+\nBINARYMODEL is.: {}
+\nNOLINES is.....: {}
+\nEXPLAIN is.....: {}
 \nlookup function name = {} 
 \nhash function name = {}
 \nword list name = {}
@@ -954,6 +1486,9 @@ impl Drop for Options {
             _option_word & OptionType::C as i32 ? "enabled" : "disabled",
             _option_word & OptionType::ANSIC as i32 ? "enabled" : "disabled",
             _option_word & OptionType::CPLUSPLUS as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::GO as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::JAVA as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::PYTHON as i32 ? "enabled" : "disabled",
             _option_word & OptionType::SEVENBIT as i32 ? "enabled" : "disabled",
             _option_word & OptionType::LENTABLE as i32 ? "enabled" : "disabled",
             _option_word & OptionType::COMP as i32 ? "enabled" : "disabled",
@@ -969,6 +1504,13 @@ impl Drop for Options {
             _option_word & OptionType::NOLENGTH as i32 ? "enabled" : "disabled",
             _option_word & OptionType::RANDOM as i32 ? "enabled" : "disabled",
             _option_word & OptionType::DEBUG as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::SELFTEST as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::REVERSE as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::LONGESTPREFIX as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::CANONICAL as i32 ? "enabled" : "disabled",
+            _option_word & OptionType::BINARYMODEL as i32 ? "enabled" : "disabled",
+            _no_lines ? "enabled" : "disabled",
+            _option_word & OptionType::EXPLAIN as i32 ? "enabled" : "disabled",
             _function_name, _hash_name, _wordlist_name, _lengthtable_name,
             _stringpool_name, _slot_name, _initializer_suffix,
             _asso_iterations, _jump, _size_multiple, _initial_asso_value,
@@ -995,6 +1537,78 @@ impl Drop for Options {
     }
 }
 
+/* Converts a NUL-terminated option string to a String.  */
+pub fn option_text(mut p: *const char) -> String {
+    let mut result: String = String::new();
+    while (*p != '\0') {
+        result.push(*p);
+        p = p.offset(1);
+    }
+    return result;
+}
+
+/* Returns ARG quoted for a POSIX shell, so that pasting it reproduces
+   ARG exactly.  The option part of "--name=value" and "-x" is left bare
+   for readability.  The result is safe inside a C comment, so it also
+   serves for file names.  */
+fn shell_quote_argument(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+
+    let prefix_len: usize = if arg.starts_with("--") {
+        arg.find('=').map_or(0, |i| i + 1)
+    } else if (arg.len() > 2 && arg.starts_with('-') && arg.as_bytes()[1].is_ascii_alphanumeric()) {
+        2
+    } else {
+        0
+    };
+    let (prefix, value) = arg.split_at(prefix_len);
+    if (!prefix.chars().all(is_safe)) {
+        return shell_quote(arg);
+    }
+    if (!value.is_empty() && value.chars().all(is_safe)) {
+        return String::from(arg);
+    }
+    return format!("{}{}", prefix, shell_quote(value));
+}
+
+/* Quotes S in single quotes.  A single quote becomes '\'', and "*" "/"
+   becomes *'/' so that the quoted text cannot end the C comment it is
+   printed in; both are the same words to the shell.  */
+fn shell_quote(s: &str) -> String {
+    let mut result: String = String::from("'");
+    let mut previous: char = '\0';
+    for c in s.chars() {
+        if (c == '\'') {
+            result.push_str("'\\''");
+        } else if (c == '/' && previous == '*') {
+            result.push_str("'/'");
+        } else {
+            result.push(c);
+        }
+        previous = c;
+    }
+    result.push('\'');
+    return result;
+}
+
+/* Returns BYTES as a string in ANSI C syntax.  A "/" after a "*" is
+   written in octal, so that the result is safe inside a C comment.  */
+fn directive_string(bytes: &[u8]) -> String {
+    let mut result: String = String::from("\"");
+    let mut previous: u8 = 0;
+    for &b in bytes.iter() {
+        match b {
+            b'"' | b'\\' => { result.push('\\'); result.push(b as char); }
+            b'/' if previous == b'*' => result.push_str("\\057"),
+            0x20..=0x7e => result.push(b as char),
+            _ => result.push_str(&format!("\\{:03o}", b))
+        }
+        previous = b;
+    }
+    result.push('"');
+    return result;
+}
+
 struct PositionStringParser {
 
     /* A pointer to the string provided by the user.  */
@@ -204,9 +204,10 @@ impl Positions {
     }
 
 
-    pub const fn print() {
+    pub const fn to_string() -> String {
+        let mut result: String = String::new();
         if _useall {
-            print!("*");
+            result.push('*');
         } else {
             let mut first: bool = true;
             let mut seen_LASTCHAR = false;
@@ -219,11 +220,11 @@ impl Positions {
                     seen_LASTCHAR = true;
                 } else {
                     if !first {
-                        print!(",");
+                        result.push(',');
                     }
-                    print("{}", *p + 1);
+                    result.push_str(&(*p + 1).to_string());
                     if count > 0 && p[-1] == *p + 1 {
-                        print!("-");
+                        result.push('-');
                         loop {
                             p -= 1;
                             count -= 1;
@@ -231,20 +232,25 @@ impl Positions {
                                 break;
                             }
                         }
-                        print!("{}", *p + 1);
+                        result.push_str(&(*p + 1).to_string());
                     }
                     first = false;
                 }
-
+                p -= 1;
             }
             if seen_LASTCHAR {
                 if !first {
-                    print!(",");
+                    result.push(',');
                 }
-                print!("$");
+                result.push('$');
             }
         }
+        return result;
+    }
 
+    /* Prints the positions in the syntax of the -k option.  */
+    pub const fn print() {
+        print!("{}", to_string());
     }
 
 }
@@ -0,0 +1,86 @@
+mod keyword_list;
+mod options;
+
+use std::ptr::null_mut;
+
+/* Generates a longest-prefix lookup function on top of the exact lookup
+   function: it returns the longest keyword that is a prefix of the
+   given buffer, trying the keyword lengths from the longest down.  */
+struct Prefix_Lookup {
+
+    /* The distinct keyword lengths, longest first.  */
+    _lengths: Vec<i32>,
+
+    /* The return type of the lookup function.  */
+    _return_type: String
+}
+
+impl Prefix_Lookup {
+
+    pub fn new(head: *mut Keyword_List, return_type: &str) -> Prefix_Lookup {
+        let mut lengths: Vec<i32> = Vec::new();
+        let mut temp: *mut Keyword_List = head;
+        while (temp != null_mut()) {
+            lengths.push((*(*temp).first())._allchars_length);
+            temp = (*temp).rest();
+        }
+        lengths.sort_by(|a, b| b.cmp(a));
+        lengths.dedup();
+
+        Prefix_Lookup {
+            _lengths: lengths,
+            _return_type: if option[TYPE] { String::from(return_type) } else { String::from("const char *") }
+        }
+    }
+
+    /* Writes the function, named after the lookup function with a
+       "_longest_prefix" suffix.  The exact lookup function is called with
+       each candidate length; this is only correct because the generated
+       comparison looks at the length table rather than at a terminating
+       NUL, which is why this mode turns on LENTABLE.  */
+    pub fn output(&self) {
+        let lookup_name: String = if option[CPLUSPLUS] {
+            format!("{}::{}", option_text(option.get_class_name()), option_text(option.get_function_name()))
+        } else {
+            option_text(option.get_function_name())
+        };
+        let function_name: String = format!("{}_longest_prefix", option_text(option.get_function_name()));
+        let const_readonly_array: &str = if option[CONST] { "const " } else { "" };
+
+        print!("\n/* Keyword lengths, longest first.  */\n");
+        print!("static {}unsigned int {}_prefix_lengths[] =\n  {{\n    ",
+               const_readonly_array, function_name);
+        for (i, length) in self._lengths.iter().enumerate() {
+            if (i > 0) {
+                print!(",{}", if i % 16 == 0 { "\n    " } else { " " });
+            }
+            print!("{}", length);
+        }
+        print!("\n  }};\n\n");
+
+        print!("{}\n", self._return_type);
+        if (option[KRC] || option[C]) {
+            print!("{} (str, len, match_len)\n     register const char *str;\n     register unsigned int len;\n     unsigned int *match_len;\n",
+                   function_name);
+        } else {
+            print!("{} (register const char *str, register unsigned int len, unsigned int *match_len)\n",
+                   function_name);
+        }
+        print!("{{\n");
+        print!("  register unsigned int i;\n\n");
+        print!("  for (i = 0; i < {}; i++)\n", self._lengths.len());
+        print!("    if ({}_prefix_lengths[i] <= len)\n", function_name);
+        print!("      {{\n");
+        print!("        {}result = {} (str, {}_prefix_lengths[i]);\n",
+               self._return_type, lookup_name, function_name);
+        print!("        if (result)\n");
+        print!("          {{\n");
+        print!("            *match_len = {}_prefix_lengths[i];\n", function_name);
+        print!("            return result;\n");
+        print!("          }}\n");
+        print!("      }}\n");
+        print!("  return 0;\n");
+        print!("}}\n");
+    }
+
+}
@@ -0,0 +1,272 @@
+mod input;
+mod keyword;
+mod keyword_list;
+mod options;
+mod positions;
+mod simulator;
+
+use std::process;
+use std::ptr::null_mut;
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::collections::{HashMap, HashSet};
+
+/* Version of the --previous file format.  */
+const PREVIOUS_VERSION: i64 = 1;
+
+/* The result of an earlier run, saved with --previous: the key positions,
+   the associated values and each keyword's hash value.  Seeding the search
+   with it keeps the hash values, and thus the word list slots, of the
+   keywords that did not change.  */
+struct Previous_Search {
+
+    /* The key positions, as stored in Positions.  */
+    _positions: Vec<i32>,
+
+    /* The increment added to the character at each key position, or empty
+       if there is none.  */
+    _alpha_inc: Vec<u32>,
+
+    _asso_values: Vec<i32>,
+
+    /* The hash value of each keyword.  */
+    _hash_values: HashMap<Vec<u8>, i32>
+}
+
+/* Writes the bytes S as JSON: a string if S is UTF-8, which any JSON
+   reader decodes to the same bytes, and otherwise an array of the byte
+   values, since JSON strings cannot hold other bytes.  */
+pub fn json_bytes(s: &[u8]) -> String {
+    let text: &str = match std::str::from_utf8(s) {
+        Ok(text) => text,
+        Err(_) => return format!("[{}]", s.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(", "))
+    };
+    let mut result: String = String::from("\"");
+    for c in text.chars() {
+        if (c == '"' || c == '\\') {
+            result.push('\\');
+            result.push(c);
+        } else if (c < ' ' || c == '\u{7f}') {
+            result.push_str(&format!("\\u{:04x}", c as u32));
+        } else {
+            result.push(c);
+        }
+    }
+    result.push('"');
+    return result;
+}
+
+impl Previous_Search {
+
+    /* Reads FILE_NAME.  Returns None if the file does not exist yet.  */
+    pub fn read(file_name: *const char) -> Option<Previous_Search> {
+        let stream: File = match File::open(option_text(file_name)) {
+            Ok(stream) => stream,
+            Err(error) if error.kind() == ErrorKind::NotFound => return None,
+            Err(error) => {
+                eprintln!("{}: cannot open {}: {}\n", program_name, file_name, error);
+                process::exit(1);
+            }
+        };
+
+        let mut reader: Json_Reader = Json_Reader::new(stream, option_text(file_name));
+        let mut result = Previous_Search {
+            _positions: Vec::new(),
+            _alpha_inc: Vec::new(),
+            _asso_values: Vec::new(),
+            _hash_values: HashMap::new()
+        };
+
+        reader.expect(b'{');
+        loop {
+            let name: Vec<u8> = reader.parse_string();
+            reader.expect(b':');
+            match &name[..] {
+                b"version" => {
+                    if (reader.parse_integer() != PREVIOUS_VERSION) {
+                        eprintln!("{}: unsupported version, regenerate it without --previous\n", file_name);
+                        process::exit(1);
+                    }
+                }
+                b"positions" => result._positions = reader.parse_integer_array(),
+                b"alpha_inc" => result._alpha_inc = reader.parse_integer_array().iter().map(|&v| v as u32).collect(),
+                b"asso_values" => result._asso_values = reader.parse_integer_array(),
+                b"keywords" => {
+                    reader.expect(b'[');
+                    if (!reader.accept(b']')) {
+                        loop {
+                            reader.expect(b'[');
+                            let keyword: Vec<u8> = reader.parse_bytes();
+                            reader.expect(b',');
+                            let hash_value: i32 = reader.parse_integer() as i32;
+                            reader.expect(b']');
+                            result._hash_values.insert(keyword, hash_value);
+                            if (reader.accept(b']')) {
+                                break;
+                            }
+                            reader.expect(b',');
+                        }
+                    }
+                }
+                _ => reader.error("unknown member")
+            }
+            if (reader.accept(b'}')) {
+                break;
+            }
+            reader.expect(b',');
+        }
+        return Some(result);
+    }
+
+    /* Saves the result of this run to FILE_NAME, for the next one.  The
+       keywords are written with json_bytes.  */
+    pub fn write(file_name: *const char, positions: &Positions, alpha_inc: &Vec<u32>,
+                 asso_values: &Vec<i32>, head: *mut KeywordExt_List) {
+        let mut stream: File = match File::create(option_text(file_name)) {
+            Ok(stream) => stream,
+            Err(_) => {
+                eprintln!("{}: cannot create {}\n", program_name, file_name);
+                process::exit(1);
+            }
+        };
+
+        let stored: &[i32] = std::slice::from_raw_parts(positions.pointer(), positions.get_size() as usize);
+        let mut text: String = format!("{{\n  \"version\": {},\n  \"positions\": [{}],\n  \"alpha_inc\": [{}],\n  \"asso_values\": [",
+                                       PREVIOUS_VERSION,
+                                       stored.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(", "),
+                                       alpha_inc.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", "));
+        for (i, value) in asso_values.iter().enumerate() {
+            text.push_str(&format!("{}{}", if i == 0 { "" } else if i % 16 == 0 { ",\n    " } else { ", " }, value));
+        }
+        text.push_str("],\n  \"keywords\": [");
+
+        let mut first: bool = true;
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let mut keyword: *mut KeywordExt = (*temp).first();
+            while (keyword != null_mut()) {
+                text.push_str(&format!("{}\n    [{}, {}]", if first { "" } else { "," },
+                                       json_bytes(&(*keyword).keyword._allchars), (*keyword)._hash_value));
+                first = false;
+                keyword = (*keyword)._duplicate_link;
+            }
+            temp = (*temp).rest();
+        }
+        text.push_str("\n  ]\n}\n");
+
+        if (stream.write_all(text.as_bytes()).is_err()) {
+            eprintln!("{}: error writing {}\n", program_name, file_name);
+            process::exit(1);
+        }
+    }
+
+    /* Returns the saved key positions, to be used instead of searching
+       for new ones.  */
+    pub fn get_positions(&self) -> Positions {
+        let mut positions: Positions = Positions::default();
+        for &pos in self._positions.iter() {
+            positions.add(pos);
+        }
+        return positions;
+    }
+
+    /* Returns the saved increments of the characters at the key
+       positions, to be used with get_positions().  */
+    pub fn get_alpha_inc(&self) -> &Vec<u32> {
+        return &self._alpha_inc;
+    }
+
+    /* Tries to extend the saved hash function to the keywords of HEAD,
+       whose selected characters come from get_positions() and
+       get_alpha_inc().  The keywords
+       that were there before keep their hash values: the associated
+       values of the characters they use are not touched.  Each new keyword
+       gets a free hash value by changing one associated value that no
+       earlier keyword depends on, counting upwards by JUMP (1 if zero)
+       up to ASSO_VALUE_MAX.  On success, sets the hash values and returns
+       the associated values; returns None if no such extension is found,
+       and the caller falls back to a full search.  */
+    pub fn extend(&self, head: *mut KeywordExt_List, alpha_unify: Vec<u32>,
+                  asso_value_max: i32) -> Option<Vec<i32>> {
+
+        let mut asso_values: Vec<i32> = self._asso_values.clone();
+        let simulator: Simulator = Simulator::new(null_mut(), self.get_positions(), self._alpha_inc.clone(),
+                                                  alpha_unify, asso_values.clone());
+        let jump: i32 = if option.get_jump() > 0 { option.get_jump() } else { 1 };
+
+        let mut old_keywords: Vec<*mut KeywordExt> = Vec::new();
+        let mut new_keywords: Vec<*mut KeywordExt> = Vec::new();
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let keyword: *mut KeywordExt = (*temp).first();
+            if (self._hash_values.contains_key(&(*keyword).keyword._allchars)) {
+                old_keywords.push(keyword);
+            } else {
+                new_keywords.push(keyword);
+            }
+            temp = (*temp).rest();
+        }
+
+        /* The old keywords pin their characters and their hash values.  */
+        let mut fixed: HashSet<u32> = HashSet::new();
+        let mut used: HashSet<i32> = HashSet::new();
+        for &keyword in old_keywords.iter() {
+            let allchars: &Vec<u8> = &(*keyword).keyword._allchars;
+            if (simulator.hash(allchars) != self._hash_values[allchars]) {
+                /* Different options or positions: the saved values do not
+                   apply.  */
+                return None;
+            }
+            for (_, c) in simulator.hash_terms(allchars) {
+                fixed.insert(c);
+            }
+            used.insert(self._hash_values[allchars]);
+            (*keyword)._hash_value = self._hash_values[allchars];
+        }
+
+        let length_term = |allchars: &Vec<u8>| -> i32 { if option[NOLENGTH] { 0 } else { allchars.len() as i32 } };
+        let hash = |asso_values: &Vec<i32>, allchars: &Vec<u8>| -> i32 {
+            length_term(allchars)
+                + simulator.hash_terms(allchars).iter().map(|&(_, c)| asso_values[c as usize]).sum::<i32>()
+        };
+
+        for &keyword in new_keywords.iter() {
+            let allchars: &Vec<u8> = &(*keyword).keyword._allchars;
+            let mut hash_value: i32 = hash(&asso_values, allchars);
+
+            if (used.contains(&hash_value)) {
+                let free: Option<u32> = simulator.hash_terms(allchars).iter()
+                                                 .map(|&(_, c)| c)
+                                                 .find(|c| !fixed.contains(c));
+                let c: u32 = match free {
+                    Some(c) => c,
+                    None => return None
+                };
+                loop {
+                    if (asso_values[c as usize] + jump > asso_value_max) {
+                        return None;
+                    }
+                    asso_values[c as usize] += jump;
+                    hash_value = hash(&asso_values, allchars);
+                    if (!used.contains(&hash_value)) {
+                        break;
+                    }
+                }
+            }
+
+            for (_, c) in simulator.hash_terms(allchars) {
+                fixed.insert(c);
+            }
+            used.insert(hash_value);
+            (*keyword)._hash_value = hash_value;
+        }
+
+        if (option[DEBUG]) {
+            eprintln!("extended the previous hash function: {} keywords kept, {} added, {} removed",
+                      old_keywords.len(), new_keywords.len(),
+                      self._hash_values.len().saturating_sub(old_keywords.len()));
+        }
+        return Some(asso_values);
+    }
+
+}
@@ -0,0 +1,227 @@
+mod keyword_list;
+mod options;
+mod struct_fields;
+
+use std::process;
+use std::ptr::{null, null_mut};
+use std::collections::HashMap;
+
+/* Generates the inverse of the lookup function: a function that maps the
+   integer id stored in each keyword's struct initializers back to the
+   keyword string.  */
+struct Reverse_Table {
+
+    /* The keywords, each with its id.  */
+    _entries: Vec<(*mut Keyword, String)>,
+
+    /* The ids as numbers, if all of them are integer literals.  */
+    _values: Option<Vec<i64>>
+}
+
+/* Largest ratio of the id range to the number of keywords for which a
+   dense array is generated instead of a switch.  */
+const MAX_HOLE_RATIO: i64 = 2;
+
+/* Parses a C integer literal.  */
+pub fn parse_integer_literal(text: &str) -> Option<i64> {
+    let text: &str = text.trim_end_matches(|c| c == 'u' || c == 'U' || c == 'l' || c == 'L');
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits.trim()),
+        None => (false, text)
+    };
+    let value: i64 = if let Some(hex) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse::<i64>().ok()?
+    };
+    return Some(if negative { -value } else { value });
+}
+
+impl Reverse_Table {
+
+    /* Collects the ids.  The id field is the one named by FIELD_NAME, or
+       else the first integer field after the key.  */
+    pub fn new(head: *mut Keyword_List, fields: &Vec<Struct_Field>, field_name: *const char) -> Reverse_Table {
+
+        if (!option[TYPE] || fields.len() < 2) {
+            eprintln!("{}: --emit-reverse-table needs --struct-type and a struct with an id field\n",
+                      program_name);
+            process::exit(1);
+        }
+
+        let index: usize = match (1..fields.len()).find(|&i| {
+            if field_name != null() {
+                fields[i]._name == option_text(field_name)
+            } else {
+                fields[i]._kind == Field_Kind::INTEGER
+            }
+        }) {
+            Some(i) if fields[i]._kind == Field_Kind::INTEGER => i - 1,
+            Some(i) => {
+                eprintln!("{}: field '{}' of type {} is not an integer, so it cannot be the id of --emit-reverse-table\n",
+                          program_name, fields[i]._name, fields[i]._type);
+                process::exit(1);
+            }
+            None if field_name != null() => {
+                eprintln!("{}: no field '{}' in the struct declaration\n", program_name, option_text(field_name));
+                process::exit(1);
+            }
+            None => {
+                eprintln!("{}: no integer id field found in the struct declaration\n", program_name);
+                process::exit(1);
+            }
+        };
+
+        let mut entries: Vec<(*mut Keyword, String)> = Vec::new();
+        let mut temp: *mut Keyword_List = head;
+        while (temp != null_mut()) {
+            let keyword: *mut Keyword = (*temp).first();
+            /* A keyword without an initializer for the id has id zero.  */
+            let id: String = (*keyword)._initializers.get(index).cloned().unwrap_or(String::from("0"));
+            entries.push((keyword, id));
+            temp = (*temp).rest();
+        }
+
+        let values: Option<Vec<i64>> = entries.iter().map(|(_, id)| parse_integer_literal(id)).collect();
+
+        let result = Reverse_Table { _entries: entries, _values: values };
+        result.check_duplicates();
+        return result;
+    }
+
+    /* Diagnoses ids that belong to more than one keyword.  Integer ids are
+       compared by their value, whatever their base, and symbolic ids by
+       their text.  */
+    fn check_duplicates(&self) {
+        let mut seen: HashMap<String, *mut Keyword> = HashMap::new();
+        let mut errors: bool = false;
+
+        for (keyword, id) in self._entries.iter() {
+            let key: String = match parse_integer_literal(id) {
+                Some(value) => value.to_string(),
+                None => String::from(id.trim())
+            };
+            if let Some(&first) = seen.get(&key) {
+                eprintln!("{}:{}: id {} of keyword \"{}\" is already used\n{}:{}: by keyword \"{}\"\n",
+                          (**keyword)._file_name, (**keyword)._lineno, id,
+                          String::from_utf8_lossy(&(**keyword)._allchars),
+                          (*first)._file_name, (*first)._lineno,
+                          String::from_utf8_lossy(&(*first)._allchars));
+                errors = true;
+            } else {
+                seen.insert(key, *keyword);
+            }
+        }
+
+        if (errors) {
+            process::exit(1);
+        }
+    }
+
+    /* Returns the range of ids and warns about the holes in it.  */
+    fn check_holes(&self, values: &Vec<i64>) -> (i64, i64) {
+        let min: i64 = *values.iter().min().unwrap();
+        let max: i64 = *values.iter().max().unwrap();
+        let holes: i64 = max - min + 1 - values.len() as i64;
+
+        if (holes > 0) {
+            let mut sorted: Vec<i64> = values.clone();
+            sorted.sort();
+            let mut missing: Vec<String> = Vec::new();
+            for pair in sorted.windows(2) {
+                if (pair[1] - pair[0] > 2) {
+                    missing.push(format!("{}-{}", pair[0] + 1, pair[1] - 1));
+                } else if (pair[1] - pair[0] == 2) {
+                    missing.push(format!("{}", pair[0] + 1));
+                }
+            }
+            eprintln!("{}: warning: {} id{} between {} and {} have no keyword: {}\n",
+                      program_name, holes, if holes == 1 { "" } else { "s" }, min, max,
+                      missing.join(","));
+        }
+        return (min, max);
+    }
+
+    /* Writes a C string literal.  */
+    fn output_string(s: &[u8]) {
+        print!("\"");
+        for &c in s {
+            if (c == b'"' || c == b'\\') {
+                print!("\\{}", c as char);
+            } else if (c >= 0x20 && c < 0x7f && c != b'?') {
+                print!("{}", c as char);
+            } else {
+                print!("\\{:03o}", c);
+            }
+        }
+        print!("\"");
+    }
+
+    /* Writes the function header, in the style of the output language.  */
+    fn output_function_header(&self, name: &str) {
+        let const_string: &str = if option[KRC] { "" } else { "const " };
+        print!("{}char *\n", const_string);
+        if (option[KRC] || option[C]) {
+            print!("{} (id)\n     int id;\n", name);
+        } else {
+            print!("{} (int id)\n", name);
+        }
+    }
+
+    /* Generates the reverse lookup function, named after the lookup
+       function with a "_reverse" suffix.  A dense array is used when all
+       ids are integer literals in a compact range, a switch otherwise.  */
+    pub fn output(&self) {
+        let function_name: String = format!("{}_reverse", option_text(option.get_function_name()));
+        let array_name: String = format!("{}_reverse", option_text(option.get_wordlist_name()));
+        let const_readonly_array: &str = if option[CONST] { "const " } else { "" };
+
+        let dense: Option<(i64, i64)> = match &self._values {
+            Some(values) if !values.is_empty() => {
+                let (min, max) = self.check_holes(values);
+                if (max - min + 1 <= MAX_HOLE_RATIO * values.len() as i64) { Some((min, max)) } else { None }
+            }
+            _ => None
+        };
+
+        print!("\n");
+        match dense {
+            Some((min, max)) => {
+                let values: &Vec<i64> = self._values.as_ref().unwrap();
+                let mut slots: Vec<*mut Keyword> = vec![null_mut(); (max - min + 1) as usize];
+                for (i, (keyword, _)) in self._entries.iter().enumerate() {
+                    slots[(values[i] - min) as usize] = *keyword;
+                }
+
+                print!("static {}char *{}[] =\n  {{\n", const_readonly_array, array_name);
+                for (i, &keyword) in slots.iter().enumerate() {
+                    print!("    ");
+                    if (keyword != null_mut()) {
+                        Reverse_Table::output_string(&(*keyword)._allchars);
+                    } else {
+                        print!("0");
+                    }
+                    print!("{} /* {} */\n", if i + 1 < slots.len() { "," } else { "" }, min + i as i64);
+                }
+                print!("  }};\n\n");
+
+                self.output_function_header(&function_name);
+                print!("{{\n  if (id >= {} && id <= {})\n    return {}[id - {}];\n  return 0;\n}}\n",
+                       min, max, array_name, min);
+            }
+            None => {
+                self.output_function_header(&function_name);
+                print!("{{\n  switch (id)\n    {{\n");
+                for (keyword, id) in self._entries.iter() {
+                    print!("      case {}:\n        return ", id);
+                    Reverse_Table::output_string(&(**keyword)._allchars);
+                    print!(";\n");
+                }
+                print!("    }}\n  return 0;\n}}\n");
+            }
+        }
+    }
+
+}
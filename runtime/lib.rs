@@ -0,0 +1,442 @@
+/* Runtime for perfect hash models written by gperf --emit-model with
+   --model-format=binary.  Loads a model from a byte slice, without
+   allocating, and looks keys up with the same hash function as the code
+   gperf would generate for it.  */
+
+#![no_std]
+
+/* The binary model format.  All integers are little-endian.
+
+     magic        4 bytes  "GPRM"
+     version      u16      MODEL_VERSION
+     flags        u16      FLAG_IGNORE_CASE | FLAG_NO_LENGTH
+     positions    u16 count, then i16 each: 0-based, -1 for the last
+                  character
+     alpha_inc    u16 count (0 if unused), then u16 each, by position
+     alpha_unify  u16 count (0 if unused), then u16 each, by character
+     asso_values  u16 count, then i32 each, by character
+     table        u32 count (max hash value + 1), then count + 1 u32
+                  offsets: the entries with hash value h are those from
+                  offsets[h] to offsets[h + 1]
+     entries      u32 count, then per entry four u32: key offset, key
+                  length, payload offset, payload length, into the pool
+     pool         u32 length, then the bytes
+
+   The payload of a keyword is its struct initializer, as written in the
+   keyword file.  */
+
+pub const MODEL_MAGIC: &[u8; 4] = b"GPRM";
+pub const MODEL_VERSION: u16 = 1;
+
+pub const FLAG_IGNORE_CASE: u16 = 1 << 0;
+pub const FLAG_NO_LENGTH: u16 = 1 << 1;
+
+/* The position standing for the last character of the key.  */
+const LASTCHAR: i16 = -1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /* The data does not start with MODEL_MAGIC.  */
+    BadMagic,
+
+    /* The model was written by an incompatible version of gperf.  */
+    UnsupportedVersion(u16),
+
+    /* The data ends in the middle of a section.  */
+    Truncated,
+
+    /* An offset or index points outside of its section.  */
+    Corrupt
+}
+
+/* A section of the model: COUNT items starting at OFFSET.  */
+#[derive(Clone, Copy)]
+struct Section {
+    offset: usize,
+    count: usize
+}
+
+/* Reads the model sections in order.  */
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end: usize = self.pos.checked_add(len).ok_or(Error::Truncated)?;
+        if end > self.data.len() {
+            return Err(Error::Truncated);
+        }
+        let result: &'a [u8] = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(result)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let b: &[u8] = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let b: &[u8] = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /* Skips a section of COUNT items of SIZE bytes.  */
+    fn section(&mut self, count: usize, size: usize) -> Result<Section, Error> {
+        let offset: usize = self.pos;
+        self.bytes(count.checked_mul(size).ok_or(Error::Truncated)?)?;
+        Ok(Section { offset, count })
+    }
+
+}
+
+/* A model loaded from a byte slice.  */
+pub struct Model<'a> {
+    data: &'a [u8],
+    flags: u16,
+    positions: Section,
+    alpha_inc: Section,
+    alpha_unify: Section,
+    asso_values: Section,
+    table: Section,
+    entries: Section,
+    pool: Section
+}
+
+impl<'a> Model<'a> {
+
+    /* Checks the layout of DATA and returns the model it holds.  */
+    pub fn parse(data: &'a [u8]) -> Result<Model<'a>, Error> {
+        let mut reader = Reader { data, pos: 0 };
+
+        if reader.bytes(4)? != MODEL_MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let version: u16 = reader.u16()?;
+        if version != MODEL_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let flags: u16 = reader.u16()?;
+
+        let count: usize = reader.u16()? as usize;
+        let positions: Section = reader.section(count, 2)?;
+        let count: usize = reader.u16()? as usize;
+        let alpha_inc: Section = reader.section(count, 2)?;
+        let count: usize = reader.u16()? as usize;
+        let alpha_unify: Section = reader.section(count, 2)?;
+        let count: usize = reader.u16()? as usize;
+        let asso_values: Section = reader.section(count, 4)?;
+        let count: usize = reader.u32()? as usize;
+        let table: Section = reader.section(count.checked_add(1).ok_or(Error::Corrupt)?, 4)?;
+        let count: usize = reader.u32()? as usize;
+        let entries: Section = reader.section(count, 16)?;
+        let count: usize = reader.u32()? as usize;
+        let pool: Section = reader.section(count, 1)?;
+
+        let model = Model {
+            data,
+            flags,
+            positions,
+            alpha_inc,
+            alpha_unify,
+            asso_values,
+            table,
+            entries,
+            pool
+        };
+        model.check()?;
+        Ok(model)
+    }
+
+    /* Checks the offsets once, so that lookups need not.  */
+    fn check(&self) -> Result<(), Error> {
+        let mut previous: u32 = 0;
+        for h in 0..self.table.count {
+            let offset: u32 = self.read_u32(self.table, h);
+            if offset < previous || offset as usize > self.entries.count {
+                return Err(Error::Corrupt);
+            }
+            previous = offset;
+        }
+        for i in 0..self.entries.count {
+            for field in [0, 2] {
+                let start: u64 = self.read_u32(self.entries, 4 * i + field) as u64;
+                let len: u64 = self.read_u32(self.entries, 4 * i + field + 1) as u64;
+                if start + len > self.pool.count as u64 {
+                    return Err(Error::Corrupt);
+                }
+            }
+        }
+        for i in 0..self.alpha_unify.count {
+            if self.read_u16(self.alpha_unify, i) as usize >= self.asso_values.count {
+                return Err(Error::Corrupt);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_u16(&self, section: Section, index: usize) -> u16 {
+        let p: usize = section.offset + 2 * index;
+        u16::from_le_bytes([self.data[p], self.data[p + 1]])
+    }
+
+    fn read_u32(&self, section: Section, index: usize) -> u32 {
+        let p: usize = section.offset + 4 * index;
+        u32::from_le_bytes([self.data[p], self.data[p + 1], self.data[p + 2], self.data[p + 3]])
+    }
+
+    fn pool_bytes(&self, entry: usize, field: usize) -> &'a [u8] {
+        let start: usize = self.pool.offset + self.read_u32(self.entries, 4 * entry + field) as usize;
+        let len: usize = self.read_u32(self.entries, 4 * entry + field + 1) as usize;
+        &self.data[start..start + len]
+    }
+
+    /* Computes the hash value of KEY, as the generated hash function does.
+       Returns None if KEY uses a character the model has no associated
+       value for, in which case it is not a keyword.  */
+    pub fn hash(&self, key: &[u8]) -> Option<u32> {
+        let mut hval: u32 = if self.flags & FLAG_NO_LENGTH != 0 { 0 } else { key.len() as u32 };
+
+        for i in 0..self.positions.count {
+            let pos: i16 = self.read_u16(self.positions, i) as i16;
+            let mut c: usize;
+            if pos == LASTCHAR {
+                c = *key.last()? as usize;
+            } else if (pos as usize) < key.len() {
+                c = key[pos as usize] as usize;
+                if (pos as usize) < self.alpha_inc.count {
+                    c += self.read_u16(self.alpha_inc, pos as usize) as usize;
+                }
+            } else {
+                continue;
+            }
+            if self.alpha_unify.count > 0 {
+                if c >= self.alpha_unify.count {
+                    return None;
+                }
+                c = self.read_u16(self.alpha_unify, c) as usize;
+            }
+            if c >= self.asso_values.count {
+                return None;
+            }
+            hval = hval.wrapping_add(self.read_u32(self.asso_values, c));
+        }
+        Some(hval)
+    }
+
+    /* Returns the payload of KEY, or None if KEY is not a keyword.  */
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        let h: usize = self.hash(key)? as usize;
+        if h + 1 >= self.table.count {
+            return None;
+        }
+        let first: usize = self.read_u32(self.table, h) as usize;
+        let last: usize = self.read_u32(self.table, h + 1) as usize;
+        for entry in first..last {
+            let keyword: &[u8] = self.pool_bytes(entry, 0);
+            let matches: bool = if self.flags & FLAG_IGNORE_CASE != 0 {
+                keyword.eq_ignore_ascii_case(key)
+            } else {
+                keyword == key
+            };
+            if matches {
+                return Some(self.pool_bytes(entry, 2));
+            }
+        }
+        None
+    }
+
+    /* Returns the number of keywords.  */
+    pub fn len(&self) -> usize {
+        self.entries.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.count == 0
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    /* A keyword of a test model.  */
+    struct Keyword {
+        key: &'static [u8],
+        payload: &'static [u8],
+        hash_value: u32
+    }
+
+    /* Encodes a model exactly as Model::write_binary in gperf's model.rs:
+       the entries sorted by hash value, then the offset of each group. */
+    fn write_binary(flags: u16, positions: &[i16], alpha_inc: &[u16], alpha_unify: &[u16],
+                    asso_values: &[i32], keywords: &[Keyword]) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(MODEL_MAGIC);
+        data.extend_from_slice(&MODEL_VERSION.to_le_bytes());
+        data.extend_from_slice(&flags.to_le_bytes());
+
+        data.extend_from_slice(&(positions.len() as u16).to_le_bytes());
+        for &pos in positions {
+            data.extend_from_slice(&pos.to_le_bytes());
+        }
+        for values in [alpha_inc, alpha_unify] {
+            data.extend_from_slice(&(values.len() as u16).to_le_bytes());
+            for &value in values {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&(asso_values.len() as u16).to_le_bytes());
+        for &value in asso_values {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut entries: Vec<&Keyword> = keywords.iter().collect();
+        entries.sort_by_key(|k| k.hash_value);
+        let table_size: usize = entries.last().map_or(0, |k| k.hash_value as usize + 1);
+        data.extend_from_slice(&(table_size as u32).to_le_bytes());
+        let mut next: usize = 0;
+        for h in 0..table_size + 1 {
+            while next < entries.len() && (entries[next].hash_value as usize) < h {
+                next += 1;
+            }
+            data.extend_from_slice(&(next as u32).to_le_bytes());
+        }
+
+        let mut pool: Vec<u8> = Vec::new();
+        data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for keyword in entries.iter() {
+            for bytes in [keyword.key, keyword.payload] {
+                data.extend_from_slice(&(pool.len() as u32).to_le_bytes());
+                data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                pool.extend_from_slice(bytes);
+            }
+        }
+        data.extend_from_slice(&(pool.len() as u32).to_le_bytes());
+        data.extend_from_slice(&pool);
+        data
+    }
+
+    /* A hash function on the first and the last character: a = 0, b = 3,
+       c = 6, d = 9.  */
+    fn asso_values() -> Vec<i32> {
+        let mut result: Vec<i32> = std::vec![0; 256];
+        for (c, value) in [(b'a', 0), (b'b', 3), (b'c', 6), (b'd', 9)] {
+            result[c as usize] = value;
+        }
+        result
+    }
+
+    const KEYWORDS: [Keyword; 4] = [
+        Keyword { key: b"ab", payload: b"1, \"ab\"", hash_value: 5 },
+        Keyword { key: b"cd", payload: b"2, \"cd\"", hash_value: 17 },
+        Keyword { key: b"bcd", payload: b"3, \"bcd\"", hash_value: 15 },
+        Keyword { key: b"a", payload: b"", hash_value: 1 }
+    ];
+
+    fn first_and_last() -> Vec<u8> {
+        write_binary(0, &[0, LASTCHAR], &[], &[], &asso_values(), &KEYWORDS)
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = first_and_last();
+        let model: Model = Model::parse(&data).unwrap();
+        assert_eq!(model.len(), KEYWORDS.len());
+        assert!(!model.is_empty());
+        for keyword in KEYWORDS.iter() {
+            assert_eq!(model.hash(keyword.key), Some(keyword.hash_value));
+            assert_eq!(model.get(keyword.key), Some(keyword.payload));
+        }
+    }
+
+    #[test]
+    fn non_keywords() {
+        let data: Vec<u8> = first_and_last();
+        let model: Model = Model::parse(&data).unwrap();
+        /* A free slot, a collision with "cd", past the table, and the
+           empty key, which has no last character.  */
+        for key in [&b"ad"[..], b"dc", b"dddd", b"", b"AB"] {
+            assert_eq!(model.get(key), None);
+        }
+        assert_eq!(model.hash(b""), None);
+    }
+
+    #[test]
+    fn ignore_case_and_no_length() {
+        let mut alpha_unify: Vec<u16> = (0..256).collect();
+        for c in b'A'..=b'Z' {
+            alpha_unify[c as usize] = c.to_ascii_lowercase() as u16;
+        }
+        let keywords: [Keyword; 2] = [
+            Keyword { key: b"ab", payload: b"x", hash_value: 3 },
+            Keyword { key: b"ba", payload: b"y", hash_value: 0 }
+        ];
+        let data: Vec<u8> = write_binary(FLAG_IGNORE_CASE | FLAG_NO_LENGTH, &[1], &[], &alpha_unify,
+                                         &asso_values(), &keywords);
+        let model: Model = Model::parse(&data).unwrap();
+        assert_eq!(model.get(b"AB"), Some(&b"x"[..]));
+        assert_eq!(model.get(b"bA"), Some(&b"y"[..]));
+        assert_eq!(model.get(b"bb"), None);
+    }
+
+    #[test]
+    fn alpha_increments() {
+        /* The first character counts as the next one: "a" as 'b'.  */
+        let keywords: [Keyword; 2] = [
+            Keyword { key: b"a", payload: b"x", hash_value: 4 },
+            Keyword { key: b"c", payload: b"y", hash_value: 10 }
+        ];
+        let data: Vec<u8> = write_binary(0, &[0], &[1], &[], &asso_values(), &keywords);
+        let model: Model = Model::parse(&data).unwrap();
+        assert_eq!(model.hash(b"a"), Some(4));
+        assert_eq!(model.get(b"c"), Some(&b"y"[..]));
+    }
+
+    #[test]
+    fn truncated() {
+        let data: Vec<u8> = first_and_last();
+        for len in 0..data.len() {
+            let error: Error = Model::parse(&data[..len]).err().unwrap();
+            assert!(error == Error::Truncated || len < 4 && error == Error::BadMagic);
+        }
+    }
+
+    #[test]
+    fn bad_header() {
+        let mut data: Vec<u8> = first_and_last();
+        data[0] = b'X';
+        assert_eq!(Model::parse(&data).err(), Some(Error::BadMagic));
+
+        let mut data: Vec<u8> = first_and_last();
+        data[4..6].copy_from_slice(&(MODEL_VERSION + 1).to_le_bytes());
+        assert_eq!(Model::parse(&data).err(), Some(Error::UnsupportedVersion(MODEL_VERSION + 1)));
+    }
+
+    #[test]
+    fn corrupt_offsets() {
+        let data: Vec<u8> = first_and_last();
+        let model: Model = Model::parse(&data).unwrap();
+
+        /* A table offset past the entries.  */
+        let mut bad: Vec<u8> = data.clone();
+        let p: usize = model.table.offset + 4 * (model.table.count - 1);
+        bad[p..p + 4].copy_from_slice(&(KEYWORDS.len() as u32 + 1).to_le_bytes());
+        assert_eq!(Model::parse(&bad).err(), Some(Error::Corrupt));
+
+        /* A key past the pool.  */
+        let mut bad: Vec<u8> = data.clone();
+        let p: usize = model.entries.offset + 4;
+        bad[p..p + 4].copy_from_slice(&(model.pool.count as u32 + 1).to_le_bytes());
+        assert_eq!(Model::parse(&bad).err(), Some(Error::Corrupt));
+    }
+
+}
@@ -0,0 +1,294 @@
+mod char_equiv;
+mod keyword_list;
+mod options;
+mod struct_fields;
+
+use std::process;
+use std::process::Command;
+use std::ptr::null_mut;
+use std::path::PathBuf;
+use std::fs::File;
+use std::io::Write;
+use std::collections::HashSet;
+
+/* Verifies the generated lookup function by compiling it, together with a
+   small driver, with the host C compiler.  The driver checks that every
+   keyword is found, with the payload of its keyword line, and that a corpus
+   of near-miss strings is rejected.  */
+struct Self_Test<'a> {
+
+    /* The keywords that must be found.  */
+    _head: *mut Keyword_List,
+
+    /* The fields of the struct declaration, with %struct-type.  */
+    _fields: &'a Vec<Struct_Field>,
+
+    /* The generated source file to be tested.  */
+    _source_file_name: *const char,
+
+    /* Scratch directory for the driver and the executable.  */
+    _tmpdir: PathBuf
+}
+
+impl<'a> Self_Test<'a> {
+
+    pub fn new(head: *mut Keyword_List, fields: &'a Vec<Struct_Field>, source_file_name: *const char) -> Self_Test<'a> {
+        Self_Test {
+            _head: head,
+            _fields: fields,
+            _source_file_name: source_file_name,
+            _tmpdir: std::env::temp_dir().join(format!("gperf-self-test-{}", process::id()))
+        }
+    }
+
+    /* Returns the keywords, in input order.  */
+    fn keywords(&self) -> Vec<Vec<u8>> {
+        let mut result: Vec<Vec<u8>> = Vec::new();
+        let mut temp: *mut Keyword_List = self._head;
+        while temp != null_mut() {
+            result.push((*(*temp).first())._allchars.clone());
+            temp = (*temp).rest();
+        }
+        return result;
+    }
+
+    /* Normalizes a string the way the generated comparison does.  */
+    pub fn fold(s: &[u8]) -> Vec<u8> {
+        if has_char_equivs() {
+            return fold(s, &char_fold_map());
+        }
+        if option[UPPERLOWER] {
+            return s.to_ascii_lowercase();
+        }
+        return s.to_vec();
+    }
+
+    /* Returns strings that differ from some keyword by one character
+       (deletion, substitution, insertion), proper prefixes and keywords
+       with a character appended.  Strings that happen to be keywords
+       themselves are left out.  */
+    pub fn near_misses(keywords: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        let known: HashSet<Vec<u8>> = keywords.iter().map(|k| Self_Test::fold(k)).collect();
+        let mut seen: HashSet<Vec<u8>> = HashSet::new();
+        let mut result: Vec<Vec<u8>> = Vec::new();
+
+        for keyword in keywords {
+            let mut candidates: Vec<Vec<u8>> = Vec::new();
+
+            for i in 0..keyword.len() {
+                /* Deletion, which also covers the prefixes and suffixes
+                   that are one character shorter.  */
+                let mut c: Vec<u8> = keyword.clone();
+                c.remove(i);
+                candidates.push(c);
+
+                /* Substitution.  */
+                let mut c: Vec<u8> = keyword.clone();
+                c[i] = if keyword[i] == b'z' { b'a' } else { keyword[i].wrapping_add(1) };
+                candidates.push(c);
+
+                /* Insertion.  */
+                let mut c: Vec<u8> = keyword.clone();
+                c.insert(i, b'x');
+                candidates.push(c);
+            }
+
+            /* Proper prefixes.  */
+            for len in 1..keyword.len() {
+                candidates.push(keyword[0..len].to_vec());
+            }
+
+            /* Suffixes appended.  */
+            for suffix in [b'x', b' '] {
+                let mut c: Vec<u8> = keyword.clone();
+                c.push(suffix);
+                candidates.push(c);
+            }
+
+            for candidate in candidates {
+                if candidate.is_empty() || known.contains(&Self_Test::fold(&candidate)) {
+                    continue;
+                }
+                if seen.insert(candidate.clone()) {
+                    result.push(candidate);
+                }
+            }
+        }
+        return result;
+    }
+
+    /* Returns the C condition under which the field FIELD of the entry
+       ENTRY holds INITIALIZER, or None if the field cannot be compared.  */
+    pub fn c_field_condition(entry: &str, field: &Struct_Field, initializer: &str) -> Option<String> {
+        let initializer: &str = initializer.trim();
+        let is_array: bool = field._type.contains('[');
+        match field._kind {
+            Field_Kind::INTEGER | Field_Kind::FLOATING | Field_Kind::POINTER if !is_array => {
+                /* Converted to the field's type, so that float fields
+                   compare equal to their double initializers.  */
+                Some(format!("{}->{} == ({}) ({})", entry, field._name, field._type, initializer))
+            }
+            Field_Kind::STRING if initializer == "0" || initializer == "NULL" => {
+                if is_array { None } else { Some(format!("{}->{} == 0", entry, field._name)) }
+            }
+            Field_Kind::STRING if is_array => Some(format!("strcmp ({}->{}, {}) == 0", entry, field._name, initializer)),
+            Field_Kind::STRING => Some(format!("{}->{} != 0 && strcmp ({}->{}, {}) == 0",
+                                               entry, field._name, entry, field._name, initializer)),
+            _ => None
+        }
+    }
+
+    /* Writes the checks of the payload of each keyword: the fields after
+       the key must hold the initializers of its keyword line, or zero.
+       Keywords that fold to the same string as another one may be mapped
+       to either entry, so they are left out.  */
+    fn write_payload_checks(&self, stream: &mut File, function_name: &str) {
+        if !option[TYPE] || self._fields.len() < 2 {
+            return;
+        }
+        let mut keywords: Vec<*mut Keyword> = Vec::new();
+        let mut temp: *mut Keyword_List = self._head;
+        while temp != null_mut() {
+            keywords.push((*temp).first());
+            temp = (*temp).rest();
+        }
+        let folded: Vec<Vec<u8>> = keywords.iter().map(|&k| Self_Test::fold(&(*k)._allchars)).collect();
+
+        for (i, &keyword) in keywords.iter().enumerate() {
+            if folded.iter().filter(|f| **f == folded[i]).count() > 1 {
+                continue;
+            }
+            let literal: String = Self_Test::c_string(&(*keyword)._allchars);
+            let entry: String = format!("({} ({}, {}))", function_name, literal, (*keyword)._allchars.len());
+            for (j, field) in self._fields[1..].iter().enumerate() {
+                let initializer: &str = (*keyword)._initializers.get(j).map_or("0", |s| s.as_str());
+                if let Some(condition) = Self_Test::c_field_condition(&entry, field, initializer) {
+                    writeln!(stream, "  if ({} != 0 && !({}))\n    {{\n      fprintf (stderr, \"wrong value of field {}: %s\\n\", {});\n      failures++;\n    }}",
+                             entry, condition, field._name, literal);
+                }
+            }
+        }
+    }
+
+    /* Writes S as a C string literal.  */
+    pub fn c_string(s: &[u8]) -> String {
+        let mut result: String = String::from("\"");
+        for &b in s {
+            if b == b'"' || b == b'\\' {
+                result.push('\\');
+                result.push(b as char);
+            } else if b >= 0x20 && b < 0x7f && b != b'?' {
+                result.push(b as char);
+            } else {
+                result.push_str(&format!("\\{:03o}", b));
+            }
+        }
+        result.push('"');
+        return result;
+    }
+
+    /* Writes the test driver, which #includes the generated code.  */
+    fn write_driver(&self, stream: &mut File, keywords: &Vec<Vec<u8>>, near_misses: &Vec<Vec<u8>>) {
+        let function_name: String = if option[CPLUSPLUS] {
+            format!("{}::{}", option.get_class_name(), option.get_function_name())
+        } else {
+            format!("{}", option.get_function_name())
+        };
+        let key: String = if option[TYPE] {
+            format!("->{}", option.get_slot_name())
+        } else {
+            String::new()
+        };
+
+        /* The driver is in the scratch directory, so a relative name would
+           not resolve.  */
+        let source_file_name: String = option_text(self._source_file_name);
+        let source: PathBuf = std::fs::canonicalize(&source_file_name).unwrap_or(PathBuf::from(&source_file_name));
+        writeln!(stream, "#include <stdio.h>\n#include <string.h>\n#include \"{}\"\n", source.display());
+        writeln!(stream, "struct probe {{ const char *str; unsigned int len; }};\n");
+
+        writeln!(stream, "static const struct probe keywords[] =\n  {{");
+        for keyword in keywords {
+            writeln!(stream, "    {{ {}, {} }},", Self_Test::c_string(keyword), keyword.len());
+        }
+        writeln!(stream, "    {{ 0, 0 }}\n  }};\n");
+
+        writeln!(stream, "static const struct probe near_misses[] =\n  {{");
+        for near_miss in near_misses {
+            writeln!(stream, "    {{ {}, {} }},", Self_Test::c_string(near_miss), near_miss.len());
+        }
+        writeln!(stream, "    {{ 0, 0 }}\n  }};\n");
+
+        writeln!(stream, "int\nmain ()\n{{\n  int failures = 0;\n  const struct probe *p;\n");
+        writeln!(stream, "  for (p = keywords; p->str; p++)\n    {{\n      if (!{} (p->str, p->len))\n        {{\n          fprintf (stderr, \"keyword not found: %s\\n\", p->str);\n          failures++;\n        }}", function_name);
+        writeln!(stream, "      else if ({} (p->str, {} (p->str, p->len){}, p->len) != 0)\n        {{\n          fprintf (stderr, \"keyword mapped to wrong entry: %s\\n\", p->str);\n          failures++;\n        }}\n    }}",
+                 if option[UPPERLOWER] { "strncasecmp" } else { "memcmp" }, function_name, key);
+        writeln!(stream, "  for (p = near_misses; p->str; p++)\n    if ({} (p->str, p->len))\n      {{\n        fprintf (stderr, \"non-keyword accepted: %s\\n\", p->str);\n        failures++;\n      }}\n", function_name);
+        self.write_payload_checks(stream, &function_name);
+        writeln!(stream, "  return failures != 0;\n}}");
+    }
+
+    /* Compiles and runs the driver.  Exits with an error if the generated
+       code does not behave as a perfect lookup for the keyword set.  */
+    pub fn run(&self) {
+        if option[KRC] || option[SWITCH] && option[DUP] {
+            eprintln!("{}: warning: self-test is not supported for this option combination",
+                      program_name);
+            return;
+        }
+        if option.get_target_charset() == TargetCharset::EBCDIC1047 {
+            /* The driver runs on the host, whose character set is not the
+               target's.  */
+            eprintln!("{}: warning: self-test skipped for --target-charset=ebcdic-1047", program_name);
+            return;
+        }
+
+        let keywords: Vec<Vec<u8>> = self.keywords();
+        let near_misses: Vec<Vec<u8>> = Self_Test::near_misses(&keywords);
+
+        if std::fs::create_dir_all(&self._tmpdir).is_err() {
+            eprintln!("{}: cannot create directory {}", program_name, self._tmpdir.display());
+            process::exit(1);
+        }
+
+        let driver_name: PathBuf = self._tmpdir.join(if option[CPLUSPLUS] { "driver.cc" } else { "driver.c" });
+        let program: PathBuf = self._tmpdir.join("driver");
+        {
+            let mut stream: File = File::create(&driver_name).expect("cannot create self-test driver");
+            self.write_driver(&mut stream, &keywords, &near_misses);
+        }
+
+        let compiler: String = if option[CPLUSPLUS] {
+            std::env::var("CXX").unwrap_or(String::from("c++"))
+        } else {
+            std::env::var("CC").unwrap_or(String::from("cc"))
+        };
+
+        let compiled = Command::new(&compiler).arg("-o").arg(&program).arg(&driver_name).status();
+        match compiled {
+            Ok(status) if status.success() => {}
+            _ => {
+                eprintln!("{}: self-test: {} failed to compile the generated code",
+                          program_name, compiler);
+                std::fs::remove_dir_all(&self._tmpdir).ok();
+                process::exit(1);
+            }
+        }
+
+        let result = Command::new(&program).status();
+        std::fs::remove_dir_all(&self._tmpdir).ok();
+        match result {
+            Ok(status) if status.success() => {
+                if option[DEBUG] {
+                    eprintln!("self-test passed: {} keywords, {} near misses",
+                              keywords.len(), near_misses.len());
+                }
+            }
+            _ => {
+                eprintln!("{}: self-test failed", program_name);
+                process::exit(1);
+            }
+        }
+    }
+
+}
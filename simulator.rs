@@ -0,0 +1,274 @@
+mod char_equiv;
+mod keyword;
+mod keyword_list;
+mod options;
+mod positions;
+mod target_charset;
+
+use std::ptr::null_mut;
+
+/* Evaluates the hash function found by the search on arbitrary strings,
+   exactly as the generated lookup function does, so that lookups can be
+   checked in-process, without compiling anything.  */
+struct Simulator {
+
+    /* The selected key positions.  */
+    _positions: Positions,
+
+    /* The increment added to the character at each key position, or empty
+       if there is none.  */
+    _alpha_inc: Vec<u32>,
+
+    /* The case unification map, or empty without --ignore-case.  */
+    _alpha_unify: Vec<u32>,
+
+    /* The associated value of each character.  */
+    _asso_values: Vec<i32>,
+
+    /* For each hash value, the keyword with that value, whose duplicates
+       are chained through _duplicate_link.  */
+    _table: Vec<*mut KeywordExt>,
+
+    _min_key_len: i32,
+    _max_key_len: i32
+}
+
+impl Simulator {
+
+    pub fn new(head: *mut KeywordExt_List, positions: Positions, alpha_inc: Vec<u32>,
+               alpha_unify: Vec<u32>, asso_values: Vec<i32>) -> Simulator {
+
+        let mut result = Simulator {
+            _positions: positions,
+            _alpha_inc: alpha_inc,
+            _alpha_unify: alpha_unify,
+            _asso_values: asso_values,
+            _table: Vec::new(),
+            _min_key_len: i32::MAX,
+            _max_key_len: 0
+        };
+
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let keyword: *mut KeywordExt = (*temp).first();
+            let hash_value: usize = (*keyword)._hash_value as usize;
+            if (hash_value >= result._table.len()) {
+                result._table.resize(hash_value + 1, null_mut());
+            }
+            /* The search leaves no two distinct keywords with the same hash
+               value, except as duplicates; keep the first, as the output
+               does, and let check() report the other.  */
+            if (result._table[hash_value] == null_mut()) {
+                result._table[hash_value] = keyword;
+            }
+            let length: i32 = (*keyword).keyword._allchars_length;
+            result._min_key_len = std::cmp::min(result._min_key_len, length);
+            result._max_key_len = std::cmp::max(result._max_key_len, length);
+            temp = (*temp).rest();
+        }
+        return result;
+    }
+
+    pub fn get_positions(&self) -> &Positions {
+        return &self._positions;
+    }
+
+    pub fn get_asso_values(&self) -> &Vec<i32> {
+        return &self._asso_values;
+    }
+
+    /* Computes the hash value of STR, as the generated hash function does.  */
+    pub fn hash(&self, str: &[u8]) -> i32 {
+        let len: i32 = if option[NOLENGTH] { 0 } else { str.len() as i32 };
+        return len + self.hash_terms(str).iter().map(|&(_, c)| self._asso_values[c as usize]).sum::<i32>();
+    }
+
+    /* Returns the terms of the hash value of STR other than its length:
+       for each key position that STR has, the position and the character
+       used to index the associated values.  */
+    pub fn hash_terms(&self, str: &[u8]) -> Vec<(i32, u32)> {
+        let len: i32 = str.len() as i32;
+        let mut terms: Vec<(i32, u32)> = Vec::new();
+
+        /* The iterator yields the last character even for the empty
+           string, which has none.  */
+        if (str.is_empty()) {
+            return terms;
+        }
+
+        let mut iter: PositionIterator = self._positions.iterator(len);
+        loop {
+            let i: i32 = iter.next();
+            if (i == PositionIterator::EOS) {
+                break;
+            }
+            let mut c: u32;
+            if (i == Positions::LASTCHAR) {
+                c = to_target(str[(len - 1) as usize]) as u32;
+            } else {
+                c = to_target(str[i as usize]) as u32;
+                if (!self._alpha_inc.is_empty()) {
+                    c += self._alpha_inc[i as usize];
+                }
+            }
+            if (!self._alpha_unify.is_empty()) {
+                c = self._alpha_unify[c as usize];
+            }
+            terms.push((i, c));
+        }
+        return terms;
+    }
+
+    /* Returns true if STR matches KEYWORD, with the generated comparison.  */
+    fn equal(str: &[u8], keyword: *mut KeywordExt) -> bool {
+        let allchars: &Vec<u8> = &(*keyword).keyword._allchars;
+        if (has_char_equivs()) {
+            let map: Vec<u8> = char_fold_map();
+            return fold(str, &map) == fold(allchars, &map);
+        }
+        if (option[UPPERLOWER]) {
+            return str.eq_ignore_ascii_case(allchars);
+        }
+        return str == &allchars[..];
+    }
+
+    /* Looks up STR as the generated lookup function does.  Returns the
+       matching keyword, whose _final_index is its word list index, or null
+       if STR is not a keyword.  */
+    pub fn lookup(&self, str: &[u8]) -> *mut KeywordExt {
+        let len: i32 = str.len() as i32;
+        if (len < self._min_key_len || len > self._max_key_len) {
+            return null_mut();
+        }
+
+        let key: i32 = self.hash(str);
+        if (key < 0 || key as usize >= self._table.len()) {
+            return null_mut();
+        }
+
+        let mut keyword: *mut KeywordExt = self._table[key as usize];
+        while (keyword != null_mut()) {
+            if (Simulator::equal(str, keyword)) {
+                return keyword;
+            }
+            keyword = (*keyword)._duplicate_link;
+        }
+        return null_mut();
+    }
+
+    /* Checks that every keyword of the list, duplicates included, hashes to
+       its recorded hash value and is found by lookup().  Reports each
+       failure on stderr.  Returns true if all keywords are found.  */
+    pub fn check(&self, head: *mut KeywordExt_List) -> bool {
+        let mut ok: bool = true;
+
+        let mut temp: *mut KeywordExt_List = head;
+        while (temp != null_mut()) {
+            let mut keyword: *mut KeywordExt = (*temp).first();
+            while (keyword != null_mut()) {
+                let allchars: &Vec<u8> = &(*keyword).keyword._allchars;
+                let hash_value: i32 = self.hash(allchars);
+                if (hash_value != (*keyword)._hash_value) {
+                    eprintln!("simulator: keyword \"{}\" hashes to {}, not {}",
+                              String::from_utf8_lossy(allchars), hash_value, (*keyword)._hash_value);
+                    ok = false;
+                } else if (self.lookup(allchars) == null_mut()) {
+                    eprintln!("simulator: keyword \"{}\" not found",
+                              String::from_utf8_lossy(allchars));
+                    ok = false;
+                }
+                keyword = (*keyword)._duplicate_link;
+            }
+            temp = (*temp).rest();
+        }
+
+        if (option[DEBUG]) {
+            eprintln!("simulator: lookup of all keywords {}", if ok { "succeeded" } else { "failed" });
+        }
+        return ok;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /* Builds the list of KEYWORDS, with the given hash values and with the
+       word list index in order.  */
+    fn keyword_list(keywords: &[(&[u8], i32)]) -> *mut KeywordExt_List {
+        let mut factory: Keyword_Factory = Keyword_Factory {};
+        let mut head: *mut KeywordExt_List = null_mut();
+        for (i, &(allchars, hash_value)) in keywords.iter().enumerate().rev() {
+            let keyword: *mut Keyword = factory.create_keyword(allchars.to_vec(), Vec::new());
+            let ext: *mut KeywordExt = Box::into_raw(Box::new(KeywordExt {
+                keyword: *Box::from_raw(keyword),
+                _selchars: std::ptr::null(),
+                _selchars_length: 0,
+                _duplicate_link: null_mut(),
+                _hash_value: hash_value,
+                _final_index: i as i32
+            }));
+            let list: *mut KeywordExt_List = Box::into_raw(Box::new(KeywordExt_List::new(ext)));
+            (*list).keyword_list._cdr = head as *mut Keyword_List;
+            head = list;
+        }
+        return head;
+    }
+
+    /* A hash function on the first and the last character: a = 0, b = 3,
+       c = 6, d = 9.  */
+    fn simulator(head: *mut KeywordExt_List) -> Simulator {
+        let mut positions: Positions = Positions::default();
+        positions.add(0);
+        positions.add(Positions::LASTCHAR);
+        let mut asso_values: Vec<i32> = vec![0; 256];
+        for (c, value) in [(b'a', 0), (b'b', 3), (b'c', 6), (b'd', 9)] {
+            asso_values[c as usize] = value;
+        }
+        return Simulator::new(head, positions, Vec::new(), Vec::new(), asso_values);
+    }
+
+    static KEYWORDS: [(&[u8], i32); 4] = [(b"ab", 5), (b"cd", 17), (b"bcd", 15), (b"a", 1)];
+
+    #[test]
+    fn hash_values() {
+        let simulator: Simulator = simulator(keyword_list(&KEYWORDS));
+        for &(allchars, hash_value) in KEYWORDS.iter() {
+            assert_eq!(simulator.hash(allchars), hash_value);
+        }
+        assert_eq!(simulator.hash(b"ad"), 11);
+    }
+
+    #[test]
+    fn hash_of_empty_string() {
+        let simulator: Simulator = simulator(keyword_list(&KEYWORDS));
+        assert!(simulator.hash_terms(b"").is_empty());
+        assert_eq!(simulator.hash(b""), 0);
+        assert!(simulator.lookup(b"") == null_mut());
+    }
+
+    #[test]
+    fn lookup_keywords() {
+        let head: *mut KeywordExt_List = keyword_list(&KEYWORDS);
+        let simulator: Simulator = simulator(head);
+        for (i, &(allchars, _)) in KEYWORDS.iter().enumerate() {
+            let keyword: *mut KeywordExt = simulator.lookup(allchars);
+            assert!(keyword != null_mut());
+            assert_eq!((*keyword)._final_index, i as i32);
+        }
+        assert!(simulator.check(head));
+    }
+
+    #[test]
+    fn lookup_non_keywords() {
+        let simulator: Simulator = simulator(keyword_list(&KEYWORDS));
+        /* Hash values out of the table, free slots, a collision with "cd"
+           (2 + 9 + 6) and lengths out of range.  */
+        for s in [&b"ad"[..], b"abc", b"dc", b"b", b"abcd"] {
+            assert!(simulator.lookup(s) == null_mut());
+        }
+    }
+
+}
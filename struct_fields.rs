@@ -0,0 +1,387 @@
+mod keyword;
+
+use std::process;
+
+/* What a struct field's initializer must look like, as far as it is
+   checked.  */
+#[derive(Clone, Copy, PartialEq)]
+enum Field_Kind {
+
+    /* Integer, character, bool and enum types.  */
+    INTEGER,
+
+    /* float and double.  */
+    FLOATING,
+
+    /* char pointers and arrays.  */
+    STRING,
+
+    /* Other pointers.  */
+    POINTER,
+
+    /* Anything else (structs, unknown typedefs): not checked.  */
+    OTHER
+}
+
+/* A field of the struct given with %struct-type.  */
+struct Struct_Field {
+
+    /* The field name.  */
+    pub _name: String,
+
+    /* The C type, e.g. "const char *" or "int[4]".  */
+    pub _type: String,
+
+    pub _kind: Field_Kind
+}
+
+/* Words that only make up integer types.  */
+static INTEGER_TYPE_WORDS: [&str; 10] =
+    ["char", "short", "int", "long", "signed", "unsigned", "_Bool", "bool", "size_t", "ptrdiff_t"];
+
+/* Returns true if WORD is a C identifier.  */
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
+/* Classifies a field from its base type words and declarator.  */
+fn field_kind(base: &Vec<&str>, pointers: usize, is_array: bool) -> Field_Kind {
+    let words: Vec<&str> = base.iter().cloned()
+        .filter(|w| *w != "const" && *w != "volatile" && *w != "struct" && *w != "union")
+        .collect();
+    let is_char: bool = words.len() == 1 && words[0] == "char"
+                        || words.len() == 2 && words.contains(&"char")
+                           && (words.contains(&"signed") || words.contains(&"unsigned"));
+
+    if (pointers == 1 && !is_array || pointers == 0 && is_array) && is_char {
+        return Field_Kind::STRING;
+    }
+    if pointers > 0 {
+        return Field_Kind::POINTER;
+    }
+    if is_array {
+        return Field_Kind::OTHER;
+    }
+    if base.first() == Some(&"enum")
+        || !words.is_empty() && words.iter().all(|w| INTEGER_TYPE_WORDS.contains(w)
+                                                  || w.starts_with("int") && w.ends_with("_t")
+                                                  || w.starts_with("uint") && w.ends_with("_t")) {
+        return Field_Kind::INTEGER;
+    }
+    if words.len() == 1 && (words[0] == "float" || words[0] == "double")
+        || words.len() == 2 && words[0] == "long" && words[1] == "double" {
+        return Field_Kind::FLOATING;
+    }
+    return Field_Kind::OTHER;
+}
+
+/* Parses the fields of STRUCT_DECL, e.g. "struct kw { const char *name;
+   int id; };".  Returns an empty list if the declaration has no body,
+   i.e. the type is defined elsewhere.  */
+pub fn parse_struct_fields(struct_decl: &str, file_name: *const char, lineno: u32) -> Vec<Struct_Field> {
+
+    let mut fields: Vec<Struct_Field> = Vec::new();
+
+    let open: usize = match struct_decl.find('{') {
+        Some(i) => i,
+        None => return fields
+    };
+    let close: usize = match struct_decl.rfind('}') {
+        Some(i) if i > open => i,
+        _ => {
+            eprintln!("{}:{}: missing '}}' in struct declaration\n", file_name, lineno);
+            process::exit(1);
+        }
+    };
+
+    /* Drop comments, then split the body into declarations.  */
+    let mut body: String = String::new();
+    let mut rest: &str = &struct_decl[open + 1..close];
+    while !rest.is_empty() {
+        if rest.starts_with("/*") {
+            rest = match rest.find("*/") { Some(i) => &rest[i + 2..], None => "" };
+            body.push(' ');
+        } else if rest.starts_with("//") {
+            rest = match rest.find('\n') { Some(i) => &rest[i..], None => "" };
+        } else {
+            let c: char = rest.chars().next().unwrap();
+            body.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    for declaration in body.split(';') {
+        let declaration: &str = declaration.trim();
+        if declaration.is_empty() {
+            continue;
+        }
+        if declaration.contains('{') || declaration.contains('(') {
+            /* Nested struct or function pointer: give up on checking.  */
+            fields.push(Struct_Field {
+                _name: String::new(),
+                _type: String::from(declaration),
+                _kind: Field_Kind::OTHER
+            });
+            continue;
+        }
+
+        /* The base type consists of the words before the first declarator;
+           further declarators follow commas.  */
+        let mut declarators = declaration.split(',');
+        let first: String = declarators.next().unwrap().replace('*', " * ");
+        let mut words: Vec<&str> = first.split_whitespace().collect();
+
+        let mut first_declarator: Vec<&str> = Vec::new();
+        while let Some(word) = words.pop() {
+            first_declarator.insert(0, word);
+            if is_identifier(word.split('[').next().unwrap()) && !word.starts_with('*') {
+                break;
+            }
+        }
+        while words.last() == Some(&"*") {
+            first_declarator.insert(0, words.pop().unwrap());
+        }
+        let base: Vec<&str> = words;
+
+        let mut all_declarators: Vec<String> = vec![first_declarator.join(" ")];
+        for declarator in declarators {
+            all_declarators.push(declarator.replace('*', " * "));
+        }
+
+        for declarator in all_declarators {
+            let pointers: usize = declarator.matches('*').count();
+            /* Drop the pointer stars and any bit-field width.  */
+            let name_part: &str = declarator.trim_start_matches(|c: char| c == '*' || c.is_whitespace())
+                                            .split(':').next().unwrap().trim();
+            let (name, array) = match name_part.find('[') {
+                Some(i) => (name_part[..i].trim(), &name_part[i..]),
+                None => (name_part, "")
+            };
+            if base.is_empty() || !is_identifier(name) {
+                eprintln!("{}:{}: cannot parse field declaration \"{}\"\n",
+                          file_name, lineno, declaration);
+                process::exit(1);
+            }
+
+            fields.push(Struct_Field {
+                _name: String::from(name),
+                _type: format!("{}{}{}", base.join(" "),
+                               if pointers > 0 { format!(" {}", "*".repeat(pointers)) } else { String::new() },
+                               array.replace(' ', "")),
+                _kind: field_kind(&base, pointers, !array.is_empty())
+            });
+        }
+    }
+
+    return fields;
+}
+
+/* Splits the rest of a keyword line into struct initializers, at commas
+   outside of string and character literals, parentheses and braces.  A
+   trailing comma is allowed, as in C initializer lists.  */
+pub fn split_initializers(rest: &str) -> Vec<String> {
+
+    let mut result: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    let mut depth: i32 = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped: bool = false;
+
+    for c in rest.chars() {
+        if let Some(q) = quote {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => { quote = Some(c); current.push(c); }
+            '(' | '{' | '[' => { depth += 1; current.push(c); }
+            ')' | '}' | ']' => { depth -= 1; current.push(c); }
+            ',' if depth == 0 => {
+                result.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(c)
+        }
+    }
+
+    if !current.trim().is_empty() {
+        result.push(current.trim().to_string());
+    }
+    return result;
+}
+
+/* Returns true if TEXT is one or more adjacent string literals.  */
+fn is_string_literal(text: &str) -> bool {
+    let mut rest: &str = text.trim();
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        if !rest.starts_with('"') {
+            return false;
+        }
+        let mut escaped: bool = false;
+        let mut end: Option<usize> = None;
+        for (i, c) in rest.char_indices().skip(1) {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                end = Some(i);
+                break;
+            }
+        }
+        match end {
+            Some(i) => rest = rest[i + 1..].trim_start(),
+            None => return false
+        }
+    }
+    return true;
+}
+
+/* Returns true if TEXT is an integer constant expression built from
+   integer and character literals, identifiers (enumerators, macros),
+   operators and parentheses.  */
+fn is_integer_expression(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i: usize = 0;
+    let mut operands: usize = 0;
+
+    while i < chars.len() {
+        let c: char = chars[i];
+        if c.is_whitespace() || "+-*/%|&^~!<>()?:".contains(c) {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                if chars[i] == '.' {
+                    return false;
+                }
+                i += 1;
+            }
+            operands += 1;
+        } else if c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != '\'' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            if i >= chars.len() {
+                return false;
+            }
+            i += 1;
+            operands += 1;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            operands += 1;
+        } else {
+            return false;
+        }
+    }
+    return operands > 0;
+}
+
+/* Returns true if TEXT is a floating-point literal, an integer
+   expression, or a signed one of these.  */
+fn is_floating_literal(text: &str) -> bool {
+    let text: &str = text.trim_start_matches(|c| c == '-' || c == '+').trim();
+    return text.parse::<f64>().is_ok()
+        || text.trim_end_matches(|c| c == 'f' || c == 'F' || c == 'l' || c == 'L').parse::<f64>().is_ok()
+        || is_integer_expression(text);
+}
+
+/* Checks that INITIALIZER is acceptable for FIELD.  */
+fn initializer_matches(field: &Struct_Field, initializer: &str) -> bool {
+    match field._kind {
+        Field_Kind::INTEGER => is_integer_expression(initializer),
+        Field_Kind::FLOATING => is_floating_literal(initializer),
+        Field_Kind::STRING => is_string_literal(initializer) || is_identifier(initializer) || initializer == "0",
+        Field_Kind::POINTER => !is_string_literal(initializer) && !initializer.is_empty(),
+        Field_Kind::OTHER => !initializer.is_empty()
+    }
+}
+
+/* Splits the rest of KEYWORD into initializers and checks them against
+   FIELDS, whose first field holds the keyword itself.  As in C, there may
+   be fewer initializers than fields; the other fields are zero.  */
+pub fn check_initializers(fields: &Vec<Struct_Field>, keyword: &mut Keyword) {
+
+    keyword._initializers = split_initializers(&String::from_utf8_lossy(&keyword._rest));
+
+    if fields.is_empty() {
+        return;
+    }
+
+    let expected: usize = fields.len() - 1;
+    if keyword._initializers.len() > expected {
+        eprintln!("{}:{}: keyword has {} initializer{}, but the struct has only {} field{} after the key\n",
+                  keyword._file_name, keyword._lineno,
+                  keyword._initializers.len(), if keyword._initializers.len() == 1 { "" } else { "s" },
+                  expected, if expected == 1 { "" } else { "s" });
+        process::exit(1);
+    }
+
+    for (field, initializer) in fields[1..].iter().zip(keyword._initializers.iter()) {
+        if !initializer_matches(field, initializer) {
+            eprintln!("{}:{}: invalid initializer \"{}\" for field '{}' of type {}\n",
+                      keyword._file_name, keyword._lineno, initializer, field._name, field._type);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn keyword(rest: &str) -> Keyword {
+        Keyword {
+            _allchars: b"if".to_vec(),
+            _allchars_length: 2,
+            _rest: rest.as_bytes().to_vec(),
+            _initializers: Vec::new(),
+            _file_name: std::ptr::null(),
+            _lineno: 1
+        }
+    }
+
+    fn fields() -> Vec<Struct_Field> {
+        return parse_struct_fields("struct kw { const char *name; int id; const char *text; };", std::ptr::null(), 1);
+    }
+
+    #[test]
+    fn trailing_comma() {
+        assert_eq!(split_initializers("1, \"a\","), vec!["1", "\"a\""]);
+        assert_eq!(split_initializers(""), Vec::<String>::new());
+        assert_eq!(split_initializers("1,,2"), vec!["1", "", "2"]);
+
+        let mut k: Keyword = keyword("7, \"then\",");
+        check_initializers(&fields(), &mut k);
+        assert_eq!(k._initializers, vec!["7", "\"then\""]);
+    }
+
+    #[test]
+    fn partial_initializers() {
+        let mut k: Keyword = keyword("7");
+        check_initializers(&fields(), &mut k);
+        assert_eq!(k._initializers, vec!["7"]);
+
+        let mut k: Keyword = keyword("");
+        check_initializers(&fields(), &mut k);
+        assert!(k._initializers.is_empty());
+    }
+
+}
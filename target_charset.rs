@@ -0,0 +1,164 @@
+mod char_equiv;
+mod keyword;
+mod keyword_list;
+mod options;
+mod positions;
+
+use std::ptr::null_mut;
+use std::sync::OnceLock;
+
+/* The execution character set of the generated code, set with
+   --target-charset.  Keywords are read as ISO-8859-1 (or ASCII) bytes,
+   but the generated code hashes the bytes of the target: the compiler
+   translates the keyword literals, and the associated values must be
+   indexed by the translated bytes.  */
+
+/* IBM-1047 to ISO-8859-1, indexed by the EBCDIC byte.  Newline (0x15)
+   maps to 0x0A, as with the z/OS C compilers.  */
+static EBCDIC_1047_TO_LATIN1: [u8; 256] = [
+    0x00, 0x01, 0x02, 0x03, 0x9C, 0x09, 0x86, 0x7F, 0x97, 0x8D, 0x8E, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+    0x10, 0x11, 0x12, 0x13, 0x9D, 0x0A, 0x08, 0x87, 0x18, 0x19, 0x92, 0x8F, 0x1C, 0x1D, 0x1E, 0x1F,
+    0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x17, 0x1B, 0x88, 0x89, 0x8A, 0x8B, 0x8C, 0x05, 0x06, 0x07,
+    0x90, 0x91, 0x16, 0x93, 0x94, 0x95, 0x96, 0x04, 0x98, 0x99, 0x9A, 0x9B, 0x14, 0x15, 0x9E, 0x1A,
+    0x20, 0xA0, 0xE2, 0xE4, 0xE0, 0xE1, 0xE3, 0xE5, 0xE7, 0xF1, 0xA2, 0x2E, 0x3C, 0x28, 0x2B, 0x7C,
+    0x26, 0xE9, 0xEA, 0xEB, 0xE8, 0xED, 0xEE, 0xEF, 0xEC, 0xDF, 0x21, 0x24, 0x2A, 0x29, 0x3B, 0x5E,
+    0x2D, 0x2F, 0xC2, 0xC4, 0xC0, 0xC1, 0xC3, 0xC5, 0xC7, 0xD1, 0xA6, 0x2C, 0x25, 0x5F, 0x3E, 0x3F,
+    0xF8, 0xC9, 0xCA, 0xCB, 0xC8, 0xCD, 0xCE, 0xCF, 0xCC, 0x60, 0x3A, 0x23, 0x40, 0x27, 0x3D, 0x22,
+    0xD8, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0xAB, 0xBB, 0xF0, 0xFD, 0xFE, 0xB1,
+    0xB0, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E, 0x6F, 0x70, 0x71, 0x72, 0xAA, 0xBA, 0xE6, 0xB8, 0xC6, 0xA4,
+    0xB5, 0x7E, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0xA1, 0xBF, 0xD0, 0x5B, 0xDE, 0xAE,
+    0xAC, 0xA3, 0xA5, 0xB7, 0xA9, 0xA7, 0xB6, 0xBC, 0xBD, 0xBE, 0xDD, 0xA8, 0xAF, 0x5D, 0xB4, 0xD7,
+    0x7B, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0xAD, 0xF4, 0xF6, 0xF2, 0xF3, 0xF5,
+    0x7D, 0x4A, 0x4B, 0x4C, 0x4D, 0x4E, 0x4F, 0x50, 0x51, 0x52, 0xB9, 0xFB, 0xFC, 0xF9, 0xFA, 0xFF,
+    0x5C, 0xF7, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0xB2, 0xD4, 0xD6, 0xD2, 0xD3, 0xD5,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0xB3, 0xDB, 0xDC, 0xD9, 0xDA, 0x9F
+];
+
+/* ISO-8859-1 to IBM-1047, the inverse of EBCDIC_1047_TO_LATIN1, built
+   on first use.  */
+static LATIN1_TO_EBCDIC_1047: OnceLock<[u8; 256]> = OnceLock::new();
+
+fn latin1_to_ebcdic_1047() -> &'static [u8; 256] {
+    return LATIN1_TO_EBCDIC_1047.get_or_init(|| {
+        let mut table: [u8; 256] = [0; 256];
+        for (e, &l) in EBCDIC_1047_TO_LATIN1.iter().enumerate() {
+            table[l as usize] = e as u8;
+        }
+        table
+    });
+}
+
+/* Returns the target byte for the input byte C.  */
+pub fn to_target(c: u8) -> u8 {
+    match option.get_target_charset() {
+        TargetCharset::EBCDIC1047 => latin1_to_ebcdic_1047()[c as usize],
+        TargetCharset::ASCII | TargetCharset::LATIN1 => c
+    }
+}
+
+/* Returns char_fold_map() for the target bytes: the representative of
+   each target byte, for compute_alpha_unify.  In EBCDIC
+   the letters are not contiguous, so case folding cannot be done by
+   offsetting 'A'..'Z'.  */
+pub fn target_fold_map() -> Vec<u8> {
+    let map: Vec<u8> = char_fold_map();
+    let mut result: Vec<u8> = vec![0; 256];
+    for c in 0..256 {
+        result[to_target(c as u8) as usize] = to_target(map[c]);
+    }
+    return result;
+}
+
+/* Returns the representative of the class of V in ALPHA_UNIFY.  */
+fn find_class(alpha_unify: &Vec<u32>, mut v: u32) -> u32 {
+    while (alpha_unify[v as usize] != v) {
+        v = alpha_unify[v as usize];
+    }
+    return v;
+}
+
+/* Returns the alpha_unify table of the search, for ALPHA_SIZE values of
+   a selected character after to_target and alpha_inc.  Wherever a
+   keyword selects a character, the values of all characters equivalent
+   to it under target_fold_map(), with the same increment, are unified:
+   an input differing from the keyword only by case or by equivalent
+   characters then hashes alike.  Returns an empty table, meaning no
+   unification, without --ignore-case and %char-equiv.  */
+pub fn compute_alpha_unify(head: *mut KeywordExt_List, positions: &Positions, alpha_inc: &Vec<u32>,
+                           alpha_size: u32) -> Vec<u32> {
+    if (!option[UPPERLOWER] && !has_char_equivs()) {
+        return Vec::new();
+    }
+
+    let map: Vec<u8> = target_fold_map();
+    let mut alpha_unify: Vec<u32> = (0..alpha_size).collect();
+
+    let mut temp: *mut KeywordExt_List = head;
+    while (temp != null_mut()) {
+        let allchars: &Vec<u8> = &(*(*temp).first()).keyword._allchars;
+        let len: i32 = allchars.len() as i32;
+        let mut iter: PositionIterator = positions.iterator(len);
+        loop {
+            let i: i32 = iter.next();
+            if (i == PositionIterator::EOS) {
+                break;
+            }
+            let (c, inc): (u8, u32) = if (i == Positions::LASTCHAR) {
+                (to_target(allchars[(len - 1) as usize]), 0)
+            } else {
+                (to_target(allchars[i as usize]), if alpha_inc.is_empty() { 0 } else { alpha_inc[i as usize] })
+            };
+            for d in 0..map.len() {
+                if (map[d] != map[c as usize] || d as u32 + inc >= alpha_size) {
+                    continue;
+                }
+                let a: u32 = find_class(&alpha_unify, d as u32 + inc);
+                let b: u32 = find_class(&alpha_unify, map[c as usize] as u32 + inc);
+                if (a != b) {
+                    alpha_unify[a.max(b) as usize] = a.min(b);
+                }
+            }
+        }
+        temp = (*temp).rest();
+    }
+
+    for v in 0..alpha_size {
+        alpha_unify[v as usize] = find_class(&alpha_unify, v);
+    }
+    return alpha_unify;
+}
+
+/* Emits a preprocessor check that the execution character set is the
+   target, if the tables depend on it, i.e. CHARSET_DEPENDENT is set
+   because some keyword characters were not given as escapes.  Compiling
+   for another character set then fails instead of producing a lookup
+   that silently finds nothing.  */
+pub fn output_charset_guard(charset_dependent: bool) {
+    if (!charset_dependent) {
+        return;
+    }
+
+    let mut conditions: Vec<String> = Vec::new();
+    for c in 0x20u8..0x7f {
+        let literal: String = match c {
+            b'\'' => String::from("'\\''"),
+            b'\\' => String::from("'\\\\'"),
+            _ => format!("'{}'", c as char)
+        };
+        conditions.push(format!("({} == {})", literal, to_target(c)));
+    }
+
+    print!("#if !(");
+    for (i, condition) in conditions.iter().enumerate() {
+        if (i > 0) {
+            print!("{}", if i % 4 == 0 { " \\\n      && " } else { " && " });
+        }
+        print!("{}", condition);
+    }
+    print!(")\n");
+    match option.get_target_charset() {
+        TargetCharset::EBCDIC1047 => print!("/* The character set is not EBCDIC 1047.  */\n"),
+        TargetCharset::ASCII | TargetCharset::LATIN1 => print!("/* The character set is not based on ISO-646.  */\n")
+    }
+    print!("#error \"gperf generated tables don't work with this execution character set.\"\n#endif\n\n");
+}